@@ -0,0 +1,192 @@
+use crate::{WorldFile, WorldFileError};
+use std::ops::Mul;
+
+impl WorldFile {
+    /// The transform which doesn't change coordinates
+    pub fn identity() -> Self {
+        WorldFile {
+            x_scale: 1.,
+            y_skew: 0.,
+            x_skew: 0.,
+            y_scale: 1.,
+            x_coord: 0.,
+            y_coord: 0.,
+        }
+    }
+
+    /// The transform which moves coordinates by `(dx, dy)`
+    pub fn from_translation(dx: f64, dy: f64) -> Self {
+        WorldFile {
+            x_coord: dx,
+            y_coord: dy,
+            ..Self::identity()
+        }
+    }
+
+    /// The transform which multiplies x coordinates by `sx`, and y coordinates by `sy`
+    ///
+    /// Returns [`WorldFileError::ZeroScale`] if either is zero.
+    pub fn from_scale(sx: f64, sy: f64) -> Result<Self, WorldFileError> {
+        Self::new(sx, 0., 0., sy, 0., 0.)
+    }
+
+    /// The transform which rotates coordinates about `(0, 0)` by `angle` radians, anticlockwise
+    /// when the y axis is up
+    pub fn from_rotation(angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        WorldFile {
+            x_scale: cos,
+            y_skew: sin,
+            x_skew: -sin,
+            y_scale: cos,
+            ..Self::identity()
+        }
+    }
+
+    /// This transform followed by `outer`, i.e. `outer * self`
+    ///
+    /// `w.compose(&outer).image_to_world(p)` is `outer.image_to_world(w.image_to_world(p))`.
+    pub fn compose(&self, outer: &WorldFile) -> WorldFile {
+        *outer * *self
+    }
+
+    /// This, with the world coordinates then moved by `(dx, dy)`
+    ///
+    /// To move the image coordinates instead, use `w * WorldFile::from_translation(dx, dy)`.
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// # let w = WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n").unwrap();
+    /// let moved = w.translate(-691200., -4576000.);
+    /// assert_eq!(moved.image_to_world((1., 1.)), (32., -32.));
+    /// ```
+    pub fn translate(&self, dx: f64, dy: f64) -> WorldFile {
+        Self::from_translation(dx, dy) * *self
+    }
+
+    /// This, with the world coordinates then scaled by `sx` & `sy`, e.g. to convert units
+    ///
+    /// Returns [`WorldFileError::ZeroScale`] if either is zero.
+    pub fn scale(&self, sx: f64, sy: f64) -> Result<WorldFile, WorldFileError> {
+        Ok(Self::from_scale(sx, sy)? * *self)
+    }
+
+    /// This, with the world coordinates then rotated about `(0, 0)` by `angle` radians,
+    /// anticlockwise
+    pub fn rotate(&self, angle: f64) -> WorldFile {
+        Self::from_rotation(angle) * *self
+    }
+}
+
+/// Composes two transforms: `a * b` applies `b` first, then `a`
+///
+/// So `(a * b).image_to_world(p)` is `a.image_to_world(b.image_to_world(p))`, as with matrices.
+/// For example, a world file for an image, multiplied by the transform from a thumbnail's
+/// pixels to the image's pixels, is the world file for the thumbnail.
+/// ```
+/// # use world_image_file::WorldFile;
+/// let w = WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n").unwrap();
+/// // Each thumbnail pixel is 4×4 image pixels, so the centre of its top left pixel is 1.5
+/// // image pixels right & down from the image's
+/// let thumbnail_to_image = WorldFile::new(4., 0., 0., 4., 1.5, 1.5).unwrap();
+/// let thumbnail = w * thumbnail_to_image;
+/// assert_eq!(thumbnail.to_string(), "128\n0\n0\n-128\n691248\n4575952\n");
+/// ```
+impl Mul for WorldFile {
+    type Output = WorldFile;
+
+    fn mul(self, rhs: WorldFile) -> WorldFile {
+        let (x_coord, y_coord) = self.image_to_world((rhs.x_coord, rhs.y_coord));
+        WorldFile {
+            x_scale: self.x_scale * rhs.x_scale + self.x_skew * rhs.y_skew,
+            x_skew: self.x_scale * rhs.x_skew + self.x_skew * rhs.y_scale,
+            y_skew: self.y_skew * rhs.x_scale + self.y_scale * rhs.y_skew,
+            y_scale: self.y_skew * rhs.x_skew + self.y_scale * rhs.y_scale,
+            x_coord,
+            y_coord,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn test_mul() {
+        let a = WorldFile::new(0.3, -0.1, 0.2, -0.4, 691200., 4576000.).unwrap();
+        let b = WorldFile::new(2., 0.5, -1., 3., 10., -20.).unwrap();
+        let c = WorldFile::from_rotation(0.7).translate(5., 6.);
+        for &p in &[(0., 0.), (1., 0.), (0., 1.), (123.5, -45.25)] {
+            let b_then_a = a.image_to_world(b.image_to_world(p));
+            for &(actual, expected) in &[
+                ((a * b).image_to_world(p), b_then_a),
+                (b.compose(&a).image_to_world(p), b_then_a),
+                (
+                    ((a * b) * c).image_to_world(p),
+                    (a * (b * c)).image_to_world(p),
+                ),
+                ((a.inverse().unwrap() * a).image_to_world(p), p),
+                ((a * a.inverse().unwrap()).image_to_world(p), p),
+            ] {
+                assert_close(actual.0, expected.0, 1e-9);
+                assert_close(actual.1, expected.1, 1e-9);
+            }
+        }
+        assert_eq!(a * WorldFile::identity(), a);
+        assert_eq!(WorldFile::identity() * a, a);
+    }
+
+    #[test]
+    fn test_builders() {
+        let p = (3., 4.);
+        assert_eq!(
+            WorldFile::from_translation(1., -2.).image_to_world(p),
+            (4., 2.)
+        );
+        assert_eq!(
+            WorldFile::from_scale(2., -0.5).unwrap().image_to_world(p),
+            (6., -2.)
+        );
+        assert!(matches!(
+            WorldFile::from_scale(0., 1.),
+            Err(WorldFileError::ZeroScale)
+        ));
+        let (x, y) = WorldFile::from_rotation(FRAC_PI_2).image_to_world(p);
+        assert_close(x, -4., 1e-9);
+        assert_close(y, 3., 1e-9);
+        assert_eq!(WorldFile::from_rotation(0.5).rotation(), 0.5);
+    }
+
+    #[test]
+    fn test_chaining() {
+        let w = WorldFile::new(32., 0., 0., -32., 691200., 4576000.).unwrap();
+
+        // Kilometres, relative to a false origin
+        let km = w
+            .translate(-600000., -4000000.)
+            .scale(0.001, 0.001)
+            .unwrap();
+        let (x, y) = km.image_to_world((0., 0.));
+        assert_close(x, 91.2, 1e-9);
+        assert_close(y, 576., 1e-9);
+        let (x, y) = km.image_to_world((10., 10.));
+        assert_close(x, 91.52, 1e-9);
+        assert_close(y, 575.68, 1e-9);
+
+        // Rotating a north-up image 90° anticlockwise about its top left pixel
+        let origin = (w.x_coord, w.y_coord);
+        let rotated = w
+            .translate(-origin.0, -origin.1)
+            .rotate(FRAC_PI_2)
+            .translate(origin.0, origin.1);
+        let (x, y) = rotated.image_to_world((0., 0.));
+        assert_close(x, origin.0, 1e-9);
+        assert_close(y, origin.1, 1e-9);
+        let (x, y) = rotated.image_to_world((1., 0.));
+        assert_close(x, origin.0, 1e-9);
+        assert_close(y, origin.1 + 32., 1e-9);
+        assert_eq!(rotated.rotation(), FRAC_PI_2);
+    }
+}
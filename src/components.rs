@@ -0,0 +1,168 @@
+use crate::{WorldFile, WorldFileError};
+
+impl WorldFile {
+    /// The distance, in world units, between the centres of neighbouring pixels in a row
+    pub fn pixel_width(&self) -> f64 {
+        self.x_scale.hypot(self.y_skew)
+    }
+
+    /// The distance, in world units, between the centres of neighbouring pixels in a column
+    pub fn pixel_height(&self) -> f64 {
+        self.x_skew.hypot(self.y_scale)
+    }
+
+    /// The pixel size `(width, height)` of the decomposition into a rotation, shear, and scale
+    ///
+    /// The linear part of the transform is `R(rotation) * [[width, height * tan(shear)], [0,
+    /// height]]`. The width is always positive, and the height is negative when rows go the
+    /// other way to the world y axis, as in a north-up image. Without shear these are
+    /// [`pixel_width`](#method.pixel_width) & [`pixel_height`](#method.pixel_height), with a
+    /// sign.
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// let w = WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n").unwrap();
+    /// assert_eq!(w.pixel_size(), (32., -32.));
+    /// assert_eq!((w.rotation(), w.shear(), w.is_flipped()), (0., 0., false));
+    /// ```
+    pub fn pixel_size(&self) -> (f64, f64) {
+        let width = self.pixel_width();
+        (width, self.determinant() / width)
+    }
+
+    /// The angle, in radians, from the world x axis to the image's rows, anticlockwise (when the
+    /// world y axis is up)
+    ///
+    /// This is between -π and π, and 0 for a north-up image.
+    pub fn rotation(&self) -> f64 {
+        self.y_skew.atan2(self.x_scale)
+    }
+
+    /// The angle, in radians, by which the image's columns aren't perpendicular to its rows
+    ///
+    /// See [`pixel_size`](#method.pixel_size). This is between -π/2 and π/2, and 0 unless the
+    /// image is skewed.
+    pub fn shear(&self) -> f64 {
+        let (sin, cos) = self.rotation().sin_cos();
+        let (_, height) = self.pixel_size();
+        ((cos * self.x_skew + sin * self.y_scale) / height).atan()
+    }
+
+    /// Whether the image is mirrored, compared to a north-up image (whose rows go down, while
+    /// the world y axis goes up), however it's rotated
+    ///
+    /// This is when the pixel height is positive, e.g. a world file with a positive `y_scale`,
+    /// or a negative `x_scale`.
+    pub fn is_flipped(&self) -> bool {
+        self.determinant() > 0.
+    }
+
+    /// Create a world file from its components, the inverse of
+    /// [`pixel_size`](#method.pixel_size), [`rotation`](#method.rotation), and
+    /// [`shear`](#method.shear)
+    ///
+    /// `origin` is the centre of the top left pixel, as `x_coord`/`y_coord`. Angles are in
+    /// radians.
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// let w = WorldFile::from_components((691200., 4576000.), (32., -32.), 0., 0.).unwrap();
+    /// assert_eq!(w.to_string(), "32\n0\n0\n-32\n691200\n4576000\n");
+    /// ```
+    pub fn from_components(
+        origin: (f64, f64),
+        pixel_size: (f64, f64),
+        rotation: f64,
+        shear: f64,
+    ) -> Result<Self, WorldFileError> {
+        let (width, height) = pixel_size;
+        let (sin, cos) = rotation.sin_cos();
+        let sheared = height * shear.tan();
+        Self::new(
+            width * cos,
+            width * sin,
+            cos * sheared - sin * height,
+            sin * sheared + cos * height,
+            origin.0,
+            origin.1,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+    use proptest::prelude::*;
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_6, PI};
+
+    #[test]
+    fn test_north_up() {
+        let w = WorldFile::new(0.5, 0., 0., -0.25, -179.75, 89.75).unwrap();
+        assert_eq!((w.pixel_width(), w.pixel_height()), (0.5, 0.25));
+        assert_eq!(w.pixel_size(), (0.5, -0.25));
+        assert_eq!((w.rotation(), w.shear()), (0., 0.));
+        assert!(!w.is_flipped());
+
+        // Rows going up
+        let w = WorldFile::new(0.5, 0., 0., 0.25, -179.75, -89.75).unwrap();
+        assert_eq!(w.pixel_size(), (0.5, 0.25));
+        assert!(w.is_flipped());
+
+        // Columns going left
+        let w = WorldFile::new(-0.5, 0., 0., -0.25, 179.75, 89.75).unwrap();
+        assert_eq!(w.pixel_size(), (0.5, 0.25));
+        assert_eq!(w.rotation(), PI);
+        assert!(w.is_flipped());
+    }
+
+    #[test]
+    fn test_rotated() {
+        // Rotated 90° clockwise, so the top of the image faces east
+        let w = WorldFile::new(0., -10., -10., 0., 1995., 2995.).unwrap();
+        assert_eq!(w.pixel_size(), (10., -10.));
+        assert_eq!(w.rotation(), -FRAC_PI_2);
+        assert_close(w.shear(), 0., 1e-9);
+        assert!(!w.is_flipped());
+
+        // Rotated 30° anticlockwise, with 2m × 3m pixels
+        let (sin, cos) = FRAC_PI_6.sin_cos();
+        let w = WorldFile::new(2. * cos, 2. * sin, 3. * sin, -3. * cos, 0., 0.).unwrap();
+        assert_close(w.pixel_width(), 2., 1e-9);
+        assert_close(w.pixel_height(), 3., 1e-9);
+        let (width, height) = w.pixel_size();
+        assert_close(width, 2., 1e-9);
+        assert_close(height, -3., 1e-9);
+        assert_close(w.rotation(), FRAC_PI_6, 1e-9);
+        assert_close(w.shear(), 0., 1e-9);
+    }
+
+    #[test]
+    fn test_sheared() {
+        // Columns lean right by 1 pixel width every 2 rows
+        let w = WorldFile::new(1., 0., 0.5, -1., 0., 0.).unwrap();
+        assert_eq!(w.pixel_size(), (1., -1.));
+        assert_eq!(w.pixel_height(), 1.25f64.sqrt());
+        assert_close(w.shear(), -0.5f64.atan(), 1e-9);
+        assert_eq!(w.rotation(), 0.);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_components_round_trip(
+            width in 0.001f64..1000.,
+            height in prop_oneof![-1000f64..-0.001, 0.001f64..1000.],
+            rotation in -3.1f64..3.1,
+            shear in -1.5f64..1.5,
+            x in -1e6f64..1e6,
+            y in -1e6f64..1e6,
+        ) {
+            let w = WorldFile::from_components((x, y), (width, height), rotation, shear).unwrap();
+            let (w2, h2) = w.pixel_size();
+            assert!((w2 - width).abs() <= 1e-9 * width);
+            assert!((h2 - height).abs() <= 1e-9 * height.abs());
+            assert!((w.rotation() - rotation).abs() <= 1e-9);
+            assert!((w.shear() - shear).abs() <= 1e-6);
+            assert_eq!(w.is_flipped(), height > 0.);
+            assert_eq!((w.x_coord, w.y_coord), (x, y));
+        }
+    }
+}
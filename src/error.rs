@@ -0,0 +1,116 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// Everything that can go wrong when reading or writing a [`WorldFile`](crate::WorldFile)
+///
+/// Line numbers are 1-based, as a text editor would show them.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WorldFileError {
+    /// Reading or writing the underlying file failed
+    Io(io::Error),
+
+    /// The file ended before this line, so not all six values are present
+    MissingLine(usize),
+
+    /// This line could not be parsed as a number
+    InvalidNumber { line: usize, text: String },
+
+    /// The transform is degenerate (e.g. a scale of zero), so it cannot be inverted
+    ZeroScale,
+
+    /// An image width or height is zero, or an image would be resized to a size of zero or less
+    InvalidImageSize,
+
+    /// A value is `NaN` or infinite
+    ///
+    /// `line` is the line of the file it's on, or `None` if it was given to (or calculated by) a
+    /// constructor instead.
+    NonFinite { line: Option<usize> },
+
+    /// There is unexpected content on this line, after the six values
+    TrailingGarbage { line: usize },
+
+    /// The value on this line is too large to be plausible
+    OutOfRange { line: usize, value: f64 },
+
+    /// No world file could be found for this image
+    SidecarNotFound(PathBuf),
+
+    /// A GDAL `.aux.xml` file is invalid, or has no georeferencing
+    InvalidAuxXml(String),
+
+    /// A TIFF file is invalid, or its GeoTIFF tags are
+    InvalidTiff(String),
+
+    /// A TIFF file has no GeoTIFF tags for an affine transform
+    NotGeoreferenced,
+
+    /// Too few ground control points were given to fit a transform
+    TooFewGcps { needed: usize, found: usize },
+
+    /// The ground control points' pixels, or world points, are all on one line (or the same
+    /// point), so no unique transform fits them
+    DegenerateGcps,
+
+    /// The options for fitting ground control points are invalid, e.g. a negative threshold
+    InvalidOptions(String),
+}
+
+impl fmt::Display for WorldFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WorldFileError::Io(e) => write!(f, "I/O error: {}", e),
+            WorldFileError::MissingLine(line) => write!(f, "line {} is missing", line),
+            WorldFileError::InvalidNumber { line, text } => {
+                write!(f, "line {}: {:?} is not a valid number", line, text)
+            }
+            WorldFileError::ZeroScale => {
+                write!(f, "the transform is degenerate and cannot be inverted")
+            }
+            WorldFileError::InvalidImageSize => write!(f, "the image size is zero or negative"),
+            WorldFileError::NonFinite { line: Some(line) } => {
+                write!(f, "line {}: value is not a finite number", line)
+            }
+            WorldFileError::NonFinite { line: None } => write!(f, "a value is not a finite number"),
+            WorldFileError::TrailingGarbage { line } => {
+                write!(f, "line {}: unexpected content after the six values", line)
+            }
+            WorldFileError::OutOfRange { line, value } => {
+                write!(f, "line {}: {} is implausibly large", line, value)
+            }
+            WorldFileError::SidecarNotFound(image) => {
+                write!(f, "no world file found for {}", image.display())
+            }
+            WorldFileError::InvalidAuxXml(msg) => write!(f, "invalid .aux.xml file: {}", msg),
+            WorldFileError::InvalidTiff(msg) => write!(f, "invalid TIFF file: {}", msg),
+            WorldFileError::NotGeoreferenced => write!(f, "TIFF file is not georeferenced"),
+            WorldFileError::TooFewGcps { needed, found } => write!(
+                f,
+                "{} ground control points are needed, but only {} were given",
+                needed, found
+            ),
+            WorldFileError::DegenerateGcps => {
+                write!(f, "the ground control points are collinear")
+            }
+            WorldFileError::InvalidOptions(msg) => write!(f, "invalid options: {}", msg),
+        }
+    }
+}
+
+impl Error for WorldFileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WorldFileError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WorldFileError {
+    fn from(e: io::Error) -> Self {
+        WorldFileError::Io(e)
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{PixelAnchor, WorldFile, WorldFileError};
+
+/// An axis-aligned rectangle in world coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl WorldFile {
+    /// Create a north-up world file for an image of this size, covering exactly these bounds
+    ///
+    /// The bounds are the outer edges of the image (e.g. the `BBOX` of a WMS `GetMap` request),
+    /// and the world file's `x_coord`/`y_coord` will be the centre of the top left pixel. If a
+    /// min is larger than the max, the image is flipped on that axis.
+    ///
+    /// Returns [`WorldFileError::InvalidImageSize`] if the width or height is zero, and
+    /// [`WorldFileError::ZeroScale`] if a min is the same as the max.
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// let w = WorldFile::from_bounds(691184., 4574416., 694384., 4576016., 100, 50).unwrap();
+    /// assert_eq!(w.to_string(), "32\n0\n0\n-32\n691200\n4576000\n");
+    /// ```
+    pub fn from_bounds(
+        min_x: f64,
+        min_y: f64,
+        max_x: f64,
+        max_y: f64,
+        width: u32,
+        height: u32,
+    ) -> Result<Self, WorldFileError> {
+        if width == 0 || height == 0 {
+            return Err(WorldFileError::InvalidImageSize);
+        }
+        let x_scale = (max_x - min_x) / f64::from(width);
+        let y_scale = (min_y - max_y) / f64::from(height);
+        Self::new(
+            x_scale,
+            0.,
+            0.,
+            y_scale,
+            min_x + x_scale / 2.,
+            max_y + y_scale / 2.,
+        )
+    }
+
+    /// The world coordinates of the 4 outer corners of an image of this size
+    ///
+    /// In image order: top left, top right, bottom right, bottom left. These are the outer edges
+    /// of the corner pixels, half a pixel further out than the pixel centres.
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// let w = WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n").unwrap();
+    /// assert_eq!(
+    ///     w.corners(100, 50),
+    ///     [(691184., 4576016.), (694384., 4576016.), (694384., 4574416.), (691184., 4574416.)]
+    /// );
+    /// ```
+    pub fn corners(&self, width: u32, height: u32) -> [(f64, f64); 4] {
+        let (width, height) = (f64::from(width), f64::from(height));
+        let corner = |x, y| self.image_to_world_with((x, y), PixelAnchor::Corner);
+        [
+            corner(0., 0.),
+            corner(width, 0.),
+            corner(width, height),
+            corner(0., height),
+        ]
+    }
+
+    /// The smallest axis-aligned rectangle, in world coordinates, which contains all of an image
+    /// of this size
+    ///
+    /// For rotated or skewed images, this is larger than the image itself.
+    pub fn bounds(&self, width: u32, height: u32) -> Bounds {
+        let corners = self.corners(width, height);
+        let mut bounds = Bounds {
+            min_x: corners[0].0,
+            min_y: corners[0].1,
+            max_x: corners[0].0,
+            max_y: corners[0].1,
+        };
+        for &(x, y) in corners[1..].iter() {
+            bounds.min_x = bounds.min_x.min(x);
+            bounds.min_y = bounds.min_y.min(y);
+            bounds.max_x = bounds.max_x.max(x);
+            bounds.max_y = bounds.max_y.max(y);
+        }
+        bounds
+    }
+
+    /// The outline of an image of this size, in world coordinates, as a closed polygon ring
+    ///
+    /// These are the [`corners`](#method.corners), with the first one repeated at the end. For
+    /// a north-up image the ring is clockwise.
+    pub fn footprint(&self, width: u32, height: u32) -> [(f64, f64); 5] {
+        let c = self.corners(width, height);
+        [c[0], c[1], c[2], c[3], c[0]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_bounds() {
+        // What `gdal_translate -a_ullr … -co WORLDFILE=YES` writes for these bounds & sizes.
+        // GDAL always writes 10 decimal places.
+        for &(bounds, width, height, gdal) in &[
+            (
+                (691184., 4574416., 694384., 4576016.),
+                100,
+                50,
+                "32.0000000000\n0.0000000000\n0.0000000000\n-32.0000000000\n691200.0000000000\n4576000.0000000000\n",
+            ),
+            (
+                (-180., -90., 180., 90.),
+                720,
+                360,
+                "0.5000000000\n0.0000000000\n0.0000000000\n-0.5000000000\n-179.7500000000\n89.7500000000\n",
+            ),
+            (
+                (0., 0., 1., 1.),
+                3,
+                3,
+                "0.3333333333\n0.0000000000\n0.0000000000\n-0.3333333333\n0.1666666667\n0.8333333333\n",
+            ),
+        ] {
+            let (min_x, min_y, max_x, max_y) = bounds;
+            let w = WorldFile::from_bounds(min_x, min_y, max_x, max_y, width, height).unwrap();
+            assert_eq!(w.to_string_with(&crate::WriteOptions::fixed(10)), gdal);
+
+            let b = w.bounds(width, height);
+            assert!((b.min_x - min_x).abs() < 1e-9 && (b.max_x - max_x).abs() < 1e-9);
+            assert!((b.min_y - min_y).abs() < 1e-9 && (b.max_y - max_y).abs() < 1e-9);
+        }
+
+        assert!(matches!(
+            WorldFile::from_bounds(0., 0., 1., 1., 0, 10),
+            Err(WorldFileError::InvalidImageSize)
+        ));
+        assert!(matches!(
+            WorldFile::from_bounds(0., 0., 0., 1., 10, 10),
+            Err(WorldFileError::ZeroScale)
+        ));
+    }
+
+    #[test]
+    fn test_north_up() {
+        let w = WorldFile::new(10., 0., 0., -10., 1005., 2995.).unwrap();
+        assert_eq!(
+            w.bounds(100, 30),
+            Bounds {
+                min_x: 1000.,
+                min_y: 2700.,
+                max_x: 2000.,
+                max_y: 3000.
+            }
+        );
+        assert_eq!(
+            w.footprint(100, 30),
+            [
+                (1000., 3000.),
+                (2000., 3000.),
+                (2000., 2700.),
+                (1000., 2700.),
+                (1000., 3000.)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flipped() {
+        // Rows go up, and columns go left
+        let w = WorldFile::new(-10., 0., 0., 10., 1995., 2705.).unwrap();
+        assert_eq!(w.corners(100, 30)[0], (2000., 2700.));
+        assert_eq!(
+            w.bounds(100, 30),
+            Bounds {
+                min_x: 1000.,
+                min_y: 2700.,
+                max_x: 2000.,
+                max_y: 3000.
+            }
+        );
+    }
+
+    #[test]
+    fn test_rotated() {
+        // Rotated 90° clockwise, so the top of the image faces east
+        let w = WorldFile::new(0., -10., -10., 0., 1995., 2995.).unwrap();
+        assert_eq!(
+            w.corners(100, 30),
+            [
+                (2000., 3000.),
+                (2000., 2000.),
+                (1700., 2000.),
+                (1700., 3000.)
+            ]
+        );
+        assert_eq!(
+            w.bounds(100, 30),
+            Bounds {
+                min_x: 1700.,
+                min_y: 2000.,
+                max_x: 2000.,
+                max_y: 3000.
+            }
+        );
+
+        // Rotated 45°, the bounds are larger than the image
+        let s = 10. * std::f64::consts::FRAC_1_SQRT_2;
+        let w = WorldFile::new(s, s, s, -s, 0., 0.).unwrap();
+        let bounds = w.bounds(10, 10);
+        assert!((bounds.max_x - bounds.min_x - 100. * 2f64.sqrt()).abs() < 1e-9);
+        assert!((bounds.max_y - bounds.min_y - 100. * 2f64.sqrt()).abs() < 1e-9);
+    }
+}
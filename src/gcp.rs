@@ -0,0 +1,808 @@
+use crate::{WorldFile, WorldFileError};
+
+/// A ground control point (GCP): `(pixel, world)`, a point's image coordinates and world
+/// coordinates
+pub type Gcp = ((f64, f64), (f64, f64));
+
+/// A world file fitted to ground control points (GCPs), and how well it fits them
+#[derive(Debug, Clone, PartialEq)]
+pub struct GcpFit {
+    pub world_file: WorldFile,
+
+    /// For each GCP, in order, the fitted world coordinates of its pixel minus its world
+    /// coordinates
+    pub residuals: Vec<(f64, f64)>,
+
+    /// The root mean square of the residuals' lengths, in world units
+    pub rmse: f64,
+}
+
+impl GcpFit {
+    /// How well this world file fits these GCPs
+    pub(crate) fn new(world_file: WorldFile, gcps: &[Gcp]) -> Self {
+        let residuals = gcps
+            .iter()
+            .map(|&(pixel, (x, y))| {
+                let fitted = world_file.image_to_world(pixel);
+                (fitted.0 - x, fitted.1 - y)
+            })
+            .collect::<Vec<_>>();
+        let sum_squares = residuals
+            .iter()
+            .map(|(dx, dy)| dx * dx + dy * dy)
+            .sum::<f64>();
+        let rmse = (sum_squares / residuals.len() as f64).sqrt();
+        GcpFit {
+            world_file,
+            residuals,
+            rmse,
+        }
+    }
+}
+
+/// How to fit a world file to ground control points with outliers, see
+/// [`WorldFile::from_gcps_robust`](crate::WorldFile::from_gcps_robust)
+///
+/// The default is 1000 iterations, with a seed of 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RobustOptions {
+    threshold: f64,
+    iterations: usize,
+    seed: u64,
+}
+
+impl RobustOptions {
+    /// GCPs whose residual is longer than `threshold`, in world units, are outliers
+    pub fn new(threshold: f64) -> Self {
+        RobustOptions {
+            threshold,
+            iterations: 1000,
+            seed: 0,
+        }
+    }
+
+    /// How many random samples of GCPs to try. More makes finding the inliers more likely when
+    /// there are many outliers.
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// The seed for choosing the random samples. The same seed always gives the same result.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// A world file fitted to ground control points (GCPs), ignoring outliers
+#[derive(Debug, Clone, PartialEq)]
+pub struct RobustGcpFit {
+    /// The least squares fit to the inliers
+    pub world_file: WorldFile,
+
+    /// For every GCP, in order, the fitted world coordinates of its pixel minus its world
+    /// coordinates
+    pub residuals: Vec<(f64, f64)>,
+
+    /// The root mean square of the inliers' residuals' lengths, in world units
+    pub rmse: f64,
+
+    /// The indexes of the GCPs which fit, in order
+    pub inliers: Vec<usize>,
+
+    /// The indexes of the GCPs which don't fit, in order
+    pub outliers: Vec<usize>,
+}
+
+/// Which kind of transform to fit to ground control points, see
+/// [`WorldFile::fit_gcps`](crate::WorldFile::fit_gcps)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GcpModel {
+    /// Any affine transform, with scale, rotation, and skew. Needs 3 GCPs, not all on one line.
+    Affine,
+
+    /// No rotation or skew, only a scale (which can differ in x & y) and a position. Needs 2
+    /// GCPs, in different rows & columns.
+    NorthUp,
+
+    /// A uniform scale, a rotation, and a position (a Helmert, or similarity, transform), with
+    /// square pixels. The image can be flipped, as north-up images usually are. Needs 2 GCPs, at
+    /// different pixels.
+    Similarity,
+
+    /// Only a position: the pixel size, rotation, and skew of this world file are kept. Needs 1
+    /// GCP.
+    Translation(WorldFile),
+}
+
+impl GcpModel {
+    /// The fewest GCPs this model can be fitted to
+    fn min_gcps(&self) -> usize {
+        match self {
+            GcpModel::Affine => 3,
+            GcpModel::NorthUp | GcpModel::Similarity => 2,
+            GcpModel::Translation(_) => 1,
+        }
+    }
+
+    /// The least squares fit to these GCPs, of which there are at least `min_gcps`
+    fn fit(&self, gcps: &[Gcp]) -> Result<WorldFile, WorldFileError> {
+        match self {
+            GcpModel::Affine => fit_affine(gcps),
+            GcpModel::NorthUp => fit_north_up(gcps),
+            GcpModel::Similarity => fit_similarity(gcps),
+            GcpModel::Translation(w) => fit_translation(gcps, w),
+        }
+    }
+}
+
+impl WorldFile {
+    /// Fit a world file to ground control points (GCPs), by least squares
+    ///
+    /// Each [`Gcp`] is `(pixel, world)`: image coordinates (as for
+    /// [`image_to_world`](#method.image_to_world)), and the world coordinates there. At least 3
+    /// GCPs are needed, and they can't all be on one line. With exactly 3 the fit is exact,
+    /// with more it minimises the sum of the squared residuals.
+    ///
+    /// Large residuals point to badly placed GCPs. See [`fit_gcps`](#method.fit_gcps) to fit
+    /// constrained transforms, e.g. without skew.
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// let gcps = [
+    ///     ((0., 0.), (691200., 4576000.)),
+    ///     ((100., 0.), (694400., 4576000.)),
+    ///     ((0., 50.), (691200., 4574400.)),
+    ///     ((100., 50.), (694400., 4574400.)),
+    /// ];
+    /// let fit = WorldFile::from_gcps(&gcps).unwrap();
+    /// assert_eq!(fit.world_file.to_string(), "32\n0\n0\n-32\n691200\n4576000\n");
+    /// assert!(fit.rmse < 1e-6);
+    /// ```
+    pub fn from_gcps(gcps: &[Gcp]) -> Result<GcpFit, WorldFileError> {
+        Self::fit_gcps(gcps, GcpModel::Affine)
+    }
+
+    /// Fit this kind of transform to ground control points (GCPs), by least squares
+    ///
+    /// As [`from_gcps`](#method.from_gcps), which is the same as [`GcpModel::Affine`]. The
+    /// residuals show how well the constrained transform fits.
+    /// ```
+    /// # use world_image_file::{WorldFile, GcpModel};
+    /// let gcps = [((0., 0.), (691200., 4576000.)), ((100., 50.), (694400., 4574400.))];
+    /// let fit = WorldFile::fit_gcps(&gcps, GcpModel::NorthUp).unwrap();
+    /// assert_eq!(fit.world_file.to_string(), "32\n0\n0\n-32\n691200\n4576000\n");
+    /// ```
+    pub fn fit_gcps(gcps: &[Gcp], model: GcpModel) -> Result<GcpFit, WorldFileError> {
+        if gcps.len() < model.min_gcps() {
+            return Err(WorldFileError::TooFewGcps {
+                needed: model.min_gcps(),
+                found: gcps.len(),
+            });
+        }
+        Ok(GcpFit::new(model.fit(gcps)?, gcps))
+    }
+
+    /// Fit a world file to ground control points (GCPs), ignoring outliers such as mis-clicks
+    ///
+    /// This uses RANSAC: the transform through 3 randomly chosen GCPs which the most GCPs fit
+    /// (within the threshold) is found, and then refined by a least squares fit
+    /// ([`from_gcps`](#method.from_gcps)) to the GCPs which fit it, the inliers. The random
+    /// choices are deterministic, given the seed.
+    ///
+    /// Returns [`WorldFileError::InvalidOptions`] if the threshold is negative or not finite, or
+    /// there are no iterations.
+    /// ```
+    /// # use world_image_file::{WorldFile, RobustOptions};
+    /// let gcps = [
+    ///     ((0., 0.), (691200., 4576000.)),
+    ///     ((100., 0.), (694400., 4576000.)),
+    ///     ((0., 50.), (691200., 4574400.)),
+    ///     ((100., 50.), (694400., 4574400.)),
+    ///     ((50., 25.), (600000., 4575200.)), // A mis-click
+    /// ];
+    /// let fit = WorldFile::from_gcps_robust(&gcps, &RobustOptions::new(10.)).unwrap();
+    /// assert_eq!(fit.world_file.to_string(), "32\n0\n0\n-32\n691200\n4576000\n");
+    /// assert_eq!(fit.outliers, vec![4]);
+    /// ```
+    pub fn from_gcps_robust(
+        gcps: &[Gcp],
+        options: &RobustOptions,
+    ) -> Result<RobustGcpFit, WorldFileError> {
+        Self::fit_gcps_robust(gcps, GcpModel::Affine, options)
+    }
+
+    /// Fit this kind of transform to ground control points (GCPs), ignoring outliers
+    ///
+    /// As [`from_gcps_robust`](#method.from_gcps_robust), with samples of as few GCPs as the
+    /// model needs.
+    pub fn fit_gcps_robust(
+        gcps: &[Gcp],
+        model: GcpModel,
+        options: &RobustOptions,
+    ) -> Result<RobustGcpFit, WorldFileError> {
+        ransac(gcps, options, model.min_gcps(), |gcps| model.fit(gcps))
+    }
+}
+
+/// The mean pixel & world coordinates, and the GCPs relative to them
+///
+/// Centring the coordinates keeps the fits accurate for large world coordinates.
+fn centre(gcps: &[Gcp]) -> ((f64, f64), (f64, f64), Vec<Gcp>) {
+    let n = gcps.len() as f64;
+    let (mut pixel_mean, mut world_mean) = ((0., 0.), (0., 0.));
+    for &(pixel, world) in gcps {
+        pixel_mean = (pixel_mean.0 + pixel.0 / n, pixel_mean.1 + pixel.1 / n);
+        world_mean = (world_mean.0 + world.0 / n, world_mean.1 + world.1 / n);
+    }
+    let centred = gcps
+        .iter()
+        .map(|&(pixel, world)| {
+            (
+                (pixel.0 - pixel_mean.0, pixel.1 - pixel_mean.1),
+                (world.0 - world_mean.0, world.1 - world_mean.1),
+            )
+        })
+        .collect();
+    (pixel_mean, world_mean, centred)
+}
+
+/// The world file with this linear part, which maps the mean pixel to the mean world point
+///
+/// A linear part that can't be inverted means the world points are degenerate, e.g. all on one
+/// line, so that's [`WorldFileError::DegenerateGcps`] rather than `ZeroScale`.
+fn through_means(
+    x_scale: f64,
+    y_skew: f64,
+    x_skew: f64,
+    y_scale: f64,
+    pixel_mean: (f64, f64),
+    world_mean: (f64, f64),
+) -> Result<WorldFile, WorldFileError> {
+    WorldFile::new(
+        x_scale,
+        y_skew,
+        x_skew,
+        y_scale,
+        world_mean.0 - x_scale * pixel_mean.0 - x_skew * pixel_mean.1,
+        world_mean.1 - y_skew * pixel_mean.0 - y_scale * pixel_mean.1,
+    )
+    .map_err(|e| match e {
+        WorldFileError::ZeroScale => WorldFileError::DegenerateGcps,
+        e => e,
+    })
+}
+
+fn fit_affine(gcps: &[Gcp]) -> Result<WorldFile, WorldFileError> {
+    let (pixel_mean, world_mean, centred) = centre(gcps);
+
+    // The normal equations, for world x & y separately
+    let (mut sxx, mut sxy, mut syy) = (0., 0., 0.);
+    let (mut sxu, mut syu, mut sxv, mut syv) = (0., 0., 0., 0.);
+    for ((x, y), (u, v)) in centred {
+        sxx += x * x;
+        sxy += x * y;
+        syy += y * y;
+        sxu += x * u;
+        syu += y * u;
+        sxv += x * v;
+        syv += y * v;
+    }
+    let det = sxx * syy - sxy * sxy;
+    if det.is_nan() || det <= sxx * syy * 1e-12 {
+        return Err(WorldFileError::DegenerateGcps);
+    }
+
+    through_means(
+        (syy * sxu - sxy * syu) / det,
+        (syy * sxv - sxy * syv) / det,
+        (sxx * syu - sxy * sxu) / det,
+        (sxx * syv - sxy * sxv) / det,
+        pixel_mean,
+        world_mean,
+    )
+}
+
+fn fit_north_up(gcps: &[Gcp]) -> Result<WorldFile, WorldFileError> {
+    let (pixel_mean, world_mean, centred) = centre(gcps);
+
+    // World x only depends on pixel x, and world y on pixel y
+    let (mut sxx, mut syy, mut sxu, mut syv) = (0., 0., 0., 0.);
+    for ((x, y), (u, v)) in centred {
+        sxx += x * x;
+        syy += y * y;
+        sxu += x * u;
+        syv += y * v;
+    }
+    if sxx.is_nan() || syy.is_nan() || sxx == 0. || syy == 0. {
+        return Err(WorldFileError::DegenerateGcps);
+    }
+    through_means(sxu / sxx, 0., 0., syv / syy, pixel_mean, world_mean)
+}
+
+fn fit_similarity(gcps: &[Gcp]) -> Result<WorldFile, WorldFileError> {
+    let (pixel_mean, world_mean, centred) = centre(gcps);
+
+    let (mut s, mut sxu, mut syv, mut sxv, mut syu, mut total) = (0., 0., 0., 0., 0., 0.);
+    for &((x, y), (u, v)) in &centred {
+        s += x * x + y * y;
+        sxu += x * u;
+        syv += y * v;
+        sxv += x * v;
+        syu += y * u;
+        total += u * u + v * v;
+    }
+    if s.is_nan() || s == 0. {
+        return Err(WorldFileError::DegenerateGcps);
+    }
+
+    // Either a rotation & scale, [a -b; b a], or that flipped, [a b; b -a]. Fit both, and use
+    // whichever fits better.
+    let sum_squares = |x_scale: f64, y_skew: f64, x_skew: f64, y_scale: f64| {
+        centred
+            .iter()
+            .map(|&((x, y), (u, v))| {
+                let dx = x_scale * x + x_skew * y - u;
+                let dy = y_skew * x + y_scale * y - v;
+                dx * dx + dy * dy
+            })
+            .sum::<f64>()
+    };
+    let (a, b) = ((sxu + syv) / s, (sxv - syu) / s);
+    let rotated = (a, b, -b, a);
+    let (a, b) = ((sxu - syv) / s, (syu + sxv) / s);
+    let flipped = (a, b, b, -a);
+
+    // Both fit 2 GCPs exactly, so prefer flipped (like a north-up image) unless it's worse
+    let tolerance = total * 1e-12;
+    let (x_scale, y_skew, x_skew, y_scale) =
+        if sum_squares(rotated.0, rotated.1, rotated.2, rotated.3) + tolerance
+            < sum_squares(flipped.0, flipped.1, flipped.2, flipped.3)
+        {
+            rotated
+        } else {
+            flipped
+        };
+    through_means(x_scale, y_skew, x_skew, y_scale, pixel_mean, world_mean)
+}
+
+fn fit_translation(gcps: &[Gcp], w: &WorldFile) -> Result<WorldFile, WorldFileError> {
+    let (pixel_mean, world_mean, _) = centre(gcps);
+    through_means(
+        w.x_scale, w.y_skew, w.x_skew, w.y_scale, pixel_mean, world_mean,
+    )
+}
+
+/// Fit a world file with RANSAC, using `fit` on samples of `sample_size` GCPs, and then on the
+/// inliers
+fn ransac(
+    gcps: &[Gcp],
+    options: &RobustOptions,
+    sample_size: usize,
+    fit: impl Fn(&[Gcp]) -> Result<WorldFile, WorldFileError>,
+) -> Result<RobustGcpFit, WorldFileError> {
+    if !(options.threshold >= 0. && options.threshold.is_finite()) {
+        return Err(WorldFileError::InvalidOptions(format!(
+            "the threshold must be a finite number, 0 or more, not {}",
+            options.threshold
+        )));
+    }
+    if options.iterations == 0 {
+        return Err(WorldFileError::InvalidOptions(
+            "there must be at least 1 iteration".to_string(),
+        ));
+    }
+    if gcps.len() < sample_size {
+        return Err(WorldFileError::TooFewGcps {
+            needed: sample_size,
+            found: gcps.len(),
+        });
+    }
+
+    // The inliers of `w`, and the sum of their squared residuals
+    let inliers_of = |w: &WorldFile| {
+        let fit = GcpFit::new(*w, gcps);
+        let mut inliers = Vec::new();
+        let mut sum_squares = 0.;
+        for (i, (dx, dy)) in fit.residuals.into_iter().enumerate() {
+            let square = dx * dx + dy * dy;
+            if square.sqrt() <= options.threshold {
+                inliers.push(i);
+                sum_squares += square;
+            }
+        }
+        (inliers, sum_squares)
+    };
+
+    let mut rng = SplitMix64(options.seed);
+    let mut best: Option<(Vec<usize>, f64)> = None;
+    let mut sample = Vec::with_capacity(sample_size);
+    for _ in 0..options.iterations {
+        sample.clear();
+        while sample.len() < sample_size {
+            let i = rng.below(gcps.len());
+            if !sample.contains(&i) {
+                sample.push(i);
+            }
+        }
+        let sample_gcps = sample.iter().map(|&i| gcps[i]).collect::<Vec<_>>();
+        // Degenerate samples (e.g. 3 collinear GCPs) are skipped
+        let w = match fit(&sample_gcps) {
+            Ok(w) => w,
+            Err(_) => continue,
+        };
+        let (inliers, sum_squares) = inliers_of(&w);
+        // More inliers is better, and then a closer fit
+        let better = match &best {
+            None => true,
+            Some((best_inliers, best_sum_squares)) => {
+                inliers.len() > best_inliers.len()
+                    || (inliers.len() == best_inliers.len() && sum_squares < *best_sum_squares)
+            }
+        };
+        if better {
+            best = Some((inliers, sum_squares));
+        }
+    }
+    let mut inliers = match best {
+        Some((inliers, _)) if inliers.len() >= sample_size => inliers,
+        _ => return Err(WorldFileError::DegenerateGcps),
+    };
+
+    // Refit to all the inliers, which can change which GCPs are inliers, until that settles
+    let mut world_file = None;
+    for _ in 0..10 {
+        let inlier_gcps = inliers.iter().map(|&i| gcps[i]).collect::<Vec<_>>();
+        let w = fit(&inlier_gcps)?;
+        world_file = Some(w);
+        let (new_inliers, _) = inliers_of(&w);
+        if new_inliers == inliers || new_inliers.len() < sample_size {
+            break;
+        }
+        inliers = new_inliers;
+    }
+    let world_file = world_file.expect("refitted at least once");
+
+    let GcpFit { residuals, .. } = GcpFit::new(world_file, gcps);
+    let sum_squares = inliers
+        .iter()
+        .map(|&i| residuals[i].0 * residuals[i].0 + residuals[i].1 * residuals[i].1)
+        .sum::<f64>();
+    let rmse = (sum_squares / inliers.len() as f64).sqrt();
+    let outliers = (0..gcps.len()).filter(|i| !inliers.contains(i)).collect();
+    Ok(RobustGcpFit {
+        world_file,
+        residuals,
+        rmse,
+        inliers,
+        outliers,
+    })
+}
+
+/// The SplitMix64 pseudo-random number generator, so results only depend on the seed
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from `0` to `n - 1`. The bias for `n` not a power of 2 is negligible here.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    #[test]
+    fn test_exact() {
+        let w = WorldFile::new(0.3, -0.1, 0.2, -0.4, 691200., 4576000.).unwrap();
+        let gcps = gcps_on(&w, &[(12., 7.), (980., 33.), (450., 610.)]);
+        let fit = WorldFile::from_gcps(&gcps).unwrap();
+        assert_world_file_close(&fit.world_file, &w);
+        assert_eq!(fit.residuals.len(), 3);
+        assert!(fit.rmse < 1e-6);
+    }
+
+    #[test]
+    fn test_least_squares() {
+        // The errors are +e, -e, -e, +e in x, which no affine transform can fit, so the best fit
+        // is the identity, and every GCP is off by e
+        let e = 0.25;
+        let gcps = [
+            ((0., 0.), (e, 0.)),
+            ((1., 0.), (1. - e, 0.)),
+            ((0., 1.), (-e, 1.)),
+            ((1., 1.), (1. + e, 1.)),
+        ];
+        let fit = WorldFile::from_gcps(&gcps).unwrap();
+        assert_eq!(
+            fit.world_file,
+            WorldFile::new(1., 0., 0., 1., 0., 0.).unwrap()
+        );
+        assert_eq!(fit.residuals, vec![(-e, 0.), (e, 0.), (e, 0.), (-e, 0.)]);
+        assert_eq!(fit.rmse, e);
+
+        // One bad GCP has the largest residual
+        let w = WorldFile::new(32., 0., 0., -32., 691200., 4576000.).unwrap();
+        let mut gcps = [(0., 0.), (100., 0.), (0., 50.), (100., 50.), (50., 25.)]
+            .iter()
+            .map(|&pixel| (pixel, w.image_to_world(pixel)))
+            .collect::<Vec<_>>();
+        gcps[4].1 .0 += 100.;
+        let fit = WorldFile::from_gcps(&gcps).unwrap();
+        let lengths = fit
+            .residuals
+            .iter()
+            .map(|(dx, dy)| dx.hypot(*dy))
+            .collect::<Vec<_>>();
+        assert!(lengths[..4].iter().all(|&l| l < lengths[4]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let gcps = [((0., 0.), (0., 0.)), ((1., 0.), (1., 0.))];
+        assert!(matches!(
+            WorldFile::from_gcps(&gcps),
+            Err(WorldFileError::TooFewGcps {
+                needed: 3,
+                found: 2
+            })
+        ));
+
+        let collinear = [
+            ((0., 0.), (0., 0.)),
+            ((1., 1.), (1., 0.)),
+            ((2., 2.), (2., 1.)),
+            ((3., 3.), (3., 1.)),
+        ];
+        assert!(matches!(
+            WorldFile::from_gcps(&collinear),
+            Err(WorldFileError::DegenerateGcps)
+        ));
+        let same = [((5., 5.), (0., 0.)); 3];
+        assert!(matches!(
+            WorldFile::from_gcps(&same),
+            Err(WorldFileError::DegenerateGcps)
+        ));
+
+        // The pixels are fine, but the world points are all on one line
+        let collinear_world = [
+            ((0., 0.), (0., 0.)),
+            ((1., 0.), (1., 1.)),
+            ((0., 1.), (1., 1.)),
+            ((1., 1.), (2., 2.)),
+        ];
+        assert!(matches!(
+            WorldFile::from_gcps(&collinear_world),
+            Err(WorldFileError::DegenerateGcps)
+        ));
+    }
+
+    /// GCPs at these pixels, exactly on `w`
+    fn gcps_on(w: &WorldFile, pixels: &[(f64, f64)]) -> Vec<Gcp> {
+        pixels
+            .iter()
+            .map(|&pixel| (pixel, w.image_to_world(pixel)))
+            .collect()
+    }
+
+    fn assert_world_file_close(a: &WorldFile, b: &WorldFile) {
+        assert_close(a.x_scale, b.x_scale, 1e-9);
+        assert_close(a.y_skew, b.y_skew, 1e-9);
+        assert_close(a.x_skew, b.x_skew, 1e-9);
+        assert_close(a.y_scale, b.y_scale, 1e-9);
+        assert_close(a.x_coord, b.x_coord, 1e-9);
+        assert_close(a.y_coord, b.y_coord, 1e-9);
+    }
+
+    #[test]
+    fn test_north_up() {
+        let w = WorldFile::new(0.5, 0., 0., -0.25, -179.75, 89.75).unwrap();
+        let pixels = [(0., 0.), (719., 359.), (100., 20.)];
+        let fit = WorldFile::fit_gcps(&gcps_on(&w, &pixels), GcpModel::NorthUp).unwrap();
+        assert_world_file_close(&fit.world_file, &w);
+        assert!(fit.rmse < 1e-9);
+
+        // A skewed image can't be fitted exactly, but there's no skew
+        let skewed = WorldFile::new(0.5, 0.01, 0., -0.25, -179.75, 89.75).unwrap();
+        let fit = WorldFile::fit_gcps(&gcps_on(&skewed, &pixels), GcpModel::NorthUp).unwrap();
+        assert_eq!((fit.world_file.x_skew, fit.world_file.y_skew), (0., 0.));
+        assert!(fit.rmse > 0.1);
+
+        let same_row = gcps_on(&w, &[(0., 5.), (10., 5.), (20., 5.)]);
+        assert!(matches!(
+            WorldFile::fit_gcps(&same_row, GcpModel::NorthUp),
+            Err(WorldFileError::DegenerateGcps)
+        ));
+        let same_world_x = [((0., 0.), (10., 5.)), ((10., 10.), (10., -5.))];
+        assert!(matches!(
+            WorldFile::fit_gcps(&same_world_x, GcpModel::NorthUp),
+            Err(WorldFileError::DegenerateGcps)
+        ));
+    }
+
+    #[test]
+    fn test_similarity() {
+        // Rotated 30°, with rows going down, as usual
+        let (sin, cos) = 30f64.to_radians().sin_cos();
+        let flipped =
+            WorldFile::new(2. * cos, -2. * sin, -2. * sin, -2. * cos, 500., 800.).unwrap();
+        // Rotated 30°, with rows going up
+        let rotated = WorldFile::new(2. * cos, 2. * sin, -2. * sin, 2. * cos, 500., 800.).unwrap();
+        let pixels = [(0., 0.), (100., 0.), (30., 70.), (80., 90.)];
+        for w in &[flipped, rotated] {
+            let fit = WorldFile::fit_gcps(&gcps_on(w, &pixels), GcpModel::Similarity).unwrap();
+            assert_world_file_close(&fit.world_file, w);
+            assert!(fit.rmse < 1e-9);
+
+            // 2 GCPs are enough, but can't tell if it's flipped
+            let fit = WorldFile::fit_gcps(&gcps_on(w, &pixels[..2]), GcpModel::Similarity).unwrap();
+            assert!(fit.rmse < 1e-9);
+            assert!(fit.world_file.determinant() < 0.);
+        }
+
+        // A north-up image with rectangular pixels is fitted with square ones
+        let w = WorldFile::new(2., 0., 0., -1., 0., 0.).unwrap();
+        let fit = WorldFile::fit_gcps(&gcps_on(&w, &pixels), GcpModel::Similarity).unwrap();
+        assert_close(fit.world_file.x_scale, -fit.world_file.y_scale, 1e-9);
+        assert!(fit.rmse > 1.);
+
+        assert!(matches!(
+            WorldFile::fit_gcps(&[((1., 1.), (0., 0.)); 2], GcpModel::Similarity),
+            Err(WorldFileError::DegenerateGcps)
+        ));
+    }
+
+    #[test]
+    fn test_translation() {
+        let base = WorldFile::new(32., 0., 0., -32., 0., 0.).unwrap();
+        let w = WorldFile::new(32., 0., 0., -32., 691200., 4576000.).unwrap();
+        let fit =
+            WorldFile::fit_gcps(&gcps_on(&w, &[(10., 20.)]), GcpModel::Translation(base)).unwrap();
+        assert_eq!(fit.world_file, w);
+
+        // The position is the average of the GCPs' positions
+        let gcps = [
+            ((0., 0.), (691200., 4576000.)),
+            ((1., 1.), (691234., 4575966.)),
+        ];
+        let fit = WorldFile::fit_gcps(&gcps, GcpModel::Translation(base)).unwrap();
+        assert_eq!(
+            fit.world_file,
+            WorldFile {
+                x_coord: 691201.,
+                y_coord: 4575999.,
+                ..w
+            }
+        );
+        assert_eq!(fit.residuals, vec![(1., -1.), (-1., 1.)]);
+
+        assert!(matches!(
+            WorldFile::fit_gcps(&[], GcpModel::Translation(base)),
+            Err(WorldFileError::TooFewGcps {
+                needed: 1,
+                found: 0
+            })
+        ));
+        assert!(matches!(
+            WorldFile::fit_gcps(&gcps[..1], GcpModel::Similarity),
+            Err(WorldFileError::TooFewGcps {
+                needed: 2,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_robust_model() {
+        let w = WorldFile::new(0.5, 0., 0., -0.5, -179.75, 89.75).unwrap();
+        let mut gcps = gcps_on(
+            &w,
+            &[(0., 0.), (700., 0.), (0., 350.), (700., 350.), (5., 5.)],
+        );
+        gcps[4].1 .1 += 10.;
+        let fit =
+            WorldFile::fit_gcps_robust(&gcps, GcpModel::NorthUp, &RobustOptions::new(0.1)).unwrap();
+        assert_eq!(fit.outliers, vec![4]);
+        assert_world_file_close(&fit.world_file, &w);
+    }
+
+    #[test]
+    fn test_splitmix64() {
+        // The first outputs for seed 0, from the reference implementation
+        let mut rng = SplitMix64(0);
+        assert_eq!(rng.next(), 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next(), 0x6e78_9e6a_a1b9_65f4);
+        assert_eq!(rng.next(), 0x06c4_5d18_8009_454f);
+    }
+
+    #[test]
+    fn test_robust() {
+        // A rotated grid of GCPs, with small errors, and two mis-clicks
+        let w = WorldFile::new(0.3, -0.1, 0.2, -0.4, 691200., 4576000.).unwrap();
+        let mut gcps = Vec::new();
+        for i in 0..5 {
+            for j in 0..4 {
+                let pixel = (f64::from(i) * 250., f64::from(j) * 200.);
+                let (x, y) = w.image_to_world(pixel);
+                let error = if (i + j) % 2 == 0 { 0.05 } else { -0.05 };
+                gcps.push((pixel, (x + error, y - error)));
+            }
+        }
+        gcps[7].1 .0 += 40.;
+        gcps[12].1 .1 -= 25.;
+
+        // A plain least squares fit is pulled off by the outliers
+        let fit = WorldFile::from_gcps(&gcps).unwrap();
+        assert!(fit.rmse > 5.);
+
+        let options = RobustOptions::new(1.).seed(42);
+        let robust = WorldFile::from_gcps_robust(&gcps, &options).unwrap();
+        assert_eq!(robust.outliers, vec![7, 12]);
+        assert_eq!(robust.inliers.len(), 18);
+        assert_eq!(robust.residuals.len(), 20);
+        assert!(robust.rmse < 0.1);
+        assert!(robust.residuals[7].0.abs() > 39.);
+        assert!((robust.world_file.x_coord - w.x_coord).abs() < 0.1);
+        assert!((robust.world_file.y_scale - w.y_scale).abs() < 1e-3);
+
+        // The same seed always gives the same result
+        assert_eq!(
+            WorldFile::from_gcps_robust(&gcps, &options).unwrap(),
+            robust
+        );
+        // Other seeds find the same outliers
+        for seed in 0..20 {
+            let other = WorldFile::from_gcps_robust(&gcps, &options.clone().seed(seed)).unwrap();
+            assert_eq!(other.outliers, vec![7, 12]);
+        }
+    }
+
+    #[test]
+    fn test_robust_errors() {
+        let options = RobustOptions::new(1.);
+        assert!(matches!(
+            WorldFile::from_gcps_robust(&[((0., 0.), (0., 0.))], &options),
+            Err(WorldFileError::TooFewGcps {
+                needed: 3,
+                found: 1
+            })
+        ));
+        let collinear = [
+            ((0., 0.), (0., 0.)),
+            ((1., 1.), (1., 0.)),
+            ((2., 2.), (2., 1.)),
+        ];
+        assert!(matches!(
+            WorldFile::from_gcps_robust(&collinear, &options),
+            Err(WorldFileError::DegenerateGcps)
+        ));
+
+        // Bad options aren't blamed on the GCPs
+        let gcps = [
+            ((0., 0.), (0., 0.)),
+            ((1., 0.), (1., 0.)),
+            ((0., 1.), (0., 1.)),
+        ];
+        for options in &[
+            RobustOptions::new(-1.),
+            RobustOptions::new(f64::NAN),
+            RobustOptions::new(f64::INFINITY),
+            RobustOptions::new(1.).iterations(0),
+        ] {
+            assert!(matches!(
+                WorldFile::from_gcps_robust(&gcps, options),
+                Err(WorldFileError::InvalidOptions(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{PixelAnchor, WorldFile, WorldFileError};
+
+impl WorldFile {
+    /// Create a world file from a GDAL `GeoTransform`
+    ///
+    /// GDAL orders the values differently, and its origin (`GT0`, `GT3`) is the top left corner
+    /// of the image, not the centre of the top left pixel.
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// let w = WorldFile::from_gdal_geotransform([691184., 32., 0., 4576016., 0., -32.]).unwrap();
+    /// assert_eq!(w.to_string(), "32\n0\n0\n-32\n691200\n4576000\n");
+    /// ```
+    pub fn from_gdal_geotransform(geotransform: [f64; 6]) -> Result<Self, WorldFileError> {
+        let [x_origin, x_scale, x_skew, y_origin, y_skew, y_scale] = geotransform;
+        Self::from_origin(
+            x_scale,
+            y_skew,
+            x_skew,
+            y_scale,
+            (x_origin, y_origin),
+            PixelAnchor::Corner,
+        )
+    }
+
+    /// Convert this world file to a GDAL `GeoTransform`
+    ///
+    /// See [`from_gdal_geotransform`](#method.from_gdal_geotransform).
+    pub fn to_gdal_geotransform(&self) -> [f64; 6] {
+        let (x_origin, y_origin) = self.origin(PixelAnchor::Corner);
+        [
+            x_origin,
+            self.x_scale,
+            self.x_skew,
+            y_origin,
+            self.y_skew,
+            self.y_scale,
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_north_up() {
+        // gdalinfo's "Origin = (440720.0, 3751320.0)", "Pixel Size = (60.0, -60.0)"
+        let geotransform = [440720., 60., 0., 3751320., 0., -60.];
+        let w = WorldFile::from_gdal_geotransform(geotransform).unwrap();
+        assert_eq!(
+            w,
+            WorldFile::new(60., 0., 0., -60., 440750., 3751290.).unwrap()
+        );
+        assert_eq!(w.to_gdal_geotransform(), geotransform);
+    }
+
+    #[test]
+    fn test_rotated() {
+        let geotransform = [1000., 3., 4., 2000., 4., -3.];
+        let w = WorldFile::from_gdal_geotransform(geotransform).unwrap();
+        assert_eq!(w, WorldFile::new(3., 4., 4., -3., 1003.5, 2000.5).unwrap());
+        assert_eq!(w.to_gdal_geotransform(), geotransform);
+
+        // GDAL's formula for the top left corner of pixel (10, 20)
+        let (x, y) = (
+            geotransform[0] + 10. * geotransform[1] + 20. * geotransform[2],
+            geotransform[3] + 10. * geotransform[4] + 20. * geotransform[5],
+        );
+        assert_eq!(w.image_to_world((9.5, 19.5)), (x, y));
+    }
+
+    #[test]
+    fn test_invalid() {
+        // GDAL's default, when a dataset has no georeferencing, is valid
+        assert!(WorldFile::from_gdal_geotransform([0., 1., 0., 0., 0., 1.]).is_ok());
+        assert!(matches!(
+            WorldFile::from_gdal_geotransform([0., 0., 0., 0., 0., 1.]),
+            Err(WorldFileError::ZeroScale)
+        ));
+    }
+}
@@ -0,0 +1,277 @@
+use crate::sidecar::{prj_path, read_for_image, Sidecars};
+use crate::{write_atomic, SidecarStyle, WorldFile, WorldFileError};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A coordinate reference system (CRS), as stored in a `.prj` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crs {
+    /// The CRS definition, usually as WKT (Well Known Text)
+    pub wkt: String,
+
+    /// The EPSG code of the CRS, if the WKT has one
+    pub epsg: Option<u32>,
+}
+
+impl Crs {
+    /// A CRS from its WKT, finding the EPSG code
+    ///
+    /// The EPSG code is the `AUTHORITY["EPSG","…"]` (WKT 1) or `ID["EPSG",…]` (WKT 2) of the
+    /// CRS itself, not of any of its parts (e.g. the datum). ESRI-style WKT usually has none.
+    /// ```
+    /// # use world_image_file::Crs;
+    /// let crs = Crs::from_wkt(r#"GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433],AUTHORITY["EPSG","4326"]]"#);
+    /// assert_eq!(crs.epsg, Some(4326));
+    /// ```
+    pub fn from_wkt(wkt: impl Into<String>) -> Self {
+        let wkt = wkt.into();
+        let epsg = epsg_code(&wkt);
+        Crs { wkt, epsg }
+    }
+}
+
+/// A world file, and the CRS of its world coordinates
+///
+/// World files have no CRS, so it's conventionally stored in a `.prj` file next to the world
+/// file, e.g. `map.prj` for `map.jpg` & `map.jgw`.
+/// ```no_run
+/// # use world_image_file::GeoreferencedImage;
+/// let (image, path) = GeoreferencedImage::for_image("scans/map.tif").unwrap();
+/// if let Some(crs) = image.crs {
+///     println!("EPSG:{:?}", crs.epsg);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoreferencedImage {
+    pub world_file: WorldFile,
+    pub crs: Option<Crs>,
+}
+
+impl GeoreferencedImage {
+    pub fn new(world_file: WorldFile, crs: Option<Crs>) -> Self {
+        GeoreferencedImage { world_file, crs }
+    }
+
+    /// Read a world file, and the `.prj` file next to it if there is one
+    ///
+    /// The `.prj` file has the same name as the world file, ignoring case, e.g. `map.prj` for
+    /// `map.jgw`.
+    pub fn from_path(p: impl AsRef<Path>) -> Result<Self, WorldFileError> {
+        let p = p.as_ref();
+        let world_file = WorldFile::from_path(p)?;
+        let crs = read_prj(&mut Sidecars::new(p))?;
+        Ok(GeoreferencedImage { world_file, crs })
+    }
+
+    /// Find, and open, the world file & `.prj` file for this image
+    ///
+    /// The world file is found as for [`WorldFile::for_image`]. If it's read from a GDAL
+    /// `.aux.xml` file with a CRS, that CRS is used, otherwise the image's `.prj` file.
+    ///
+    /// Returns the path the world file was read from.
+    pub fn for_image(image_path: impl AsRef<Path>) -> Result<(Self, PathBuf), WorldFileError> {
+        let mut sidecars = Sidecars::new(image_path.as_ref());
+        let (world_file, srs, path) = read_for_image(&mut sidecars)?;
+        let crs = match srs {
+            Some(srs) => Some(Crs::from_wkt(srs)),
+            None => read_prj(&mut sidecars)?,
+        };
+        Ok((GeoreferencedImage { world_file, crs }, path))
+    }
+
+    /// Write the world file to this path, and the CRS (if any) to a `.prj` file next to it
+    ///
+    /// Both files are written atomically. Without a CRS, an existing `.prj` file is left as is.
+    pub fn write_to_path(&self, p: impl AsRef<Path>) -> Result<(), WorldFileError> {
+        let p = p.as_ref();
+        self.world_file.write_to_path_atomic(p)?;
+        if let Some(crs) = &self.crs {
+            write_atomic(&prj_path(p), crs.wkt.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Write the world file & `.prj` file for this image, with the conventional file names
+    ///
+    /// See [`WorldFile::write_for_image`]. Returns the path of the world file.
+    pub fn write_for_image(
+        &self,
+        image_path: impl AsRef<Path>,
+        style: SidecarStyle,
+    ) -> Result<PathBuf, WorldFileError> {
+        self.world_file.write_for_image(
+            image_path,
+            style,
+            self.crs.as_ref().map(|crs| crs.wkt.as_str()),
+        )
+    }
+}
+
+/// Read the `.prj` file next to this image or world file, if there is one
+fn read_prj(sidecars: &mut Sidecars) -> Result<Option<Crs>, WorldFileError> {
+    let prj = match sidecars.find_prj()? {
+        Some(prj) => fs::read_to_string(prj)?,
+        None => return Ok(None),
+    };
+    let wkt = prj.trim_start_matches('\u{feff}').trim();
+    if wkt.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(Crs::from_wkt(wkt)))
+}
+
+/// The EPSG code from the outermost `AUTHORITY`/`ID` of this WKT
+fn epsg_code(wkt: &str) -> Option<u32> {
+    let mut depth = 0;
+    let mut in_quotes = false;
+    let mut i = 0;
+    while i < wkt.len() {
+        let c = wkt.as_bytes()[i];
+        if in_quotes {
+            // A `""` inside a string is an escaped quote, which this handles as 2 strings
+            in_quotes = c != b'"';
+            i += 1;
+            continue;
+        }
+        match c {
+            b'"' => in_quotes = true,
+            b'[' | b'(' => depth += 1,
+            b']' | b')' => depth -= 1,
+            c if depth == 1 && c.is_ascii_alphabetic() => {
+                let end = wkt[i..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .map_or(wkt.len(), |len| i + len);
+                let keyword = &wkt[i..end];
+                let rest = wkt[end..].trim_start();
+                if (keyword.eq_ignore_ascii_case("AUTHORITY") || keyword.eq_ignore_ascii_case("ID"))
+                    && rest.starts_with(&['[', '('][..])
+                {
+                    if let Some(code) = authority_code(&rest[1..]) {
+                        return Some(code);
+                    }
+                }
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The code from the contents of an `AUTHORITY[…]`/`ID[…]`, if the authority is EPSG
+fn authority_code(contents: &str) -> Option<u32> {
+    let mut parts = contents.splitn(3, &[',', ']', ')'][..]);
+    let name = parts.next()?.trim().trim_matches('"');
+    let code = parts.next()?.trim().trim_matches('"');
+    if name.eq_ignore_ascii_case("EPSG") {
+        code.parse().ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_dir;
+
+    #[test]
+    fn test_epsg() {
+        // From `gdalsrsinfo -o wkt1 EPSG:32611`, shortened
+        let wkt1 = r#"PROJCS["WGS 84 / UTM zone 11N",GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563,AUTHORITY["EPSG","7030"]],AUTHORITY["EPSG","6326"]],PRIMEM["Greenwich",0,AUTHORITY["EPSG","8901"]],UNIT["degree",0.0174532925199433,AUTHORITY["EPSG","9122"]],AUTHORITY["EPSG","4326"]],PROJECTION["Transverse_Mercator"],PARAMETER["central_meridian",-117],UNIT["metre",1,AUTHORITY["EPSG","9001"]],AXIS["Easting",EAST],AXIS["Northing",NORTH],AUTHORITY["EPSG","32611"]]"#;
+        assert_eq!(epsg_code(wkt1), Some(32611));
+
+        // From `gdalsrsinfo -o wkt2 EPSG:32611`, shortened
+        let wkt2 = r#"PROJCRS["WGS 84 / UTM zone 11N",
+    BASEGEOGCRS["WGS 84",
+        DATUM["World Geodetic System 1984",
+            ELLIPSOID["WGS 84",6378137,298.257223563,
+                LENGTHUNIT["metre",1]]],
+        ID["EPSG",4326]],
+    CONVERSION["UTM zone 11N",
+        METHOD["Transverse Mercator",
+            ID["EPSG",9807]]],
+    USAGE[
+        SCOPE["Navigation and medium accuracy spatial referencing."],
+        BBOX[0,-120,84,-114]],
+    ID["EPSG",32611]]"#;
+        assert_eq!(epsg_code(wkt2), Some(32611));
+
+        // ESRI WKT, as written by ArcGIS, has no authority
+        let esri = r#"PROJCS["WGS_1984_UTM_Zone_11N",GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]],PROJECTION["Transverse_Mercator"],UNIT["Meter",1.0]]"#;
+        assert_eq!(epsg_code(esri), None);
+
+        assert_eq!(
+            epsg_code(
+                r#"GEOGCS["A ""quoted"" ID[""EPSG"",1]",AUTHORITY["ESRI","104000"],ID["epsg","4258"]]"#
+            ),
+            Some(4258)
+        );
+        assert_eq!(epsg_code(r#"LOCAL_CS["x",AUTHORITY["EPSG","x"]]"#), None);
+        assert_eq!(epsg_code(""), None);
+    }
+
+    #[test]
+    fn test_path() {
+        let dir = test_dir("georeferenced");
+        let w = WorldFile::new(32., 0., 0., -32., 691200., 4576000.).unwrap();
+        let wkt = r#"PROJCS["WGS 84 / UTM zone 11N",AUTHORITY["EPSG","32611"]]"#;
+        let image = GeoreferencedImage::new(w, Some(Crs::from_wkt(wkt)));
+
+        let path = dir.join("map.tfw");
+        image.write_to_path(&path).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("map.prj")).unwrap(), wkt);
+        let read = GeoreferencedImage::from_path(&path).unwrap();
+        assert_eq!(read, image);
+        assert_eq!(read.crs.unwrap().epsg, Some(32611));
+
+        assert_eq!(
+            GeoreferencedImage::for_image(dir.join("map.tif")).unwrap(),
+            (image, path)
+        );
+
+        // Trailing newlines, and a BOM, are ignored, and case doesn't matter
+        fs::remove_file(dir.join("map.prj")).unwrap();
+        fs::write(dir.join("MAP.PRJ"), format!("\u{feff}{}\r\n", wkt)).unwrap();
+        let read = GeoreferencedImage::from_path(dir.join("map.tfw")).unwrap();
+        assert_eq!(read.crs.unwrap().wkt, wkt);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_for_image() {
+        let dir = test_dir("georeferenced_for_image");
+        let w = WorldFile::new(0.5, 0., 0., -0.5, -179.75, 89.75).unwrap();
+        let image = dir.join("map.png");
+
+        let path = GeoreferencedImage::new(w, None)
+            .write_for_image(&image, SidecarStyle::Wld)
+            .unwrap();
+        assert_eq!(path, dir.join("map.wld"));
+        assert!(!dir.join("map.prj").exists());
+        assert_eq!(
+            GeoreferencedImage::for_image(&image).unwrap(),
+            (GeoreferencedImage::new(w, None), path.clone())
+        );
+
+        // The CRS in a GDAL .aux.xml file is used, rather than a .prj file
+        fs::remove_file(&path).unwrap();
+        fs::write(dir.join("map.prj"), "LOCAL_CS[\"prj\"]").unwrap();
+        let wkt = r#"GEOGCS["WGS 84",AUTHORITY["EPSG","4326"]]"#;
+        w.write_aux_xml_path(dir.join("map.png.aux.xml"), Some(wkt))
+            .unwrap();
+        let (read, path) = GeoreferencedImage::for_image(&image).unwrap();
+        assert_eq!(path, dir.join("map.png.aux.xml"));
+        assert_eq!(read.crs, Some(Crs::from_wkt(wkt)));
+
+        w.write_aux_xml_path(dir.join("map.png.aux.xml"), None)
+            .unwrap();
+        let (read, _) = GeoreferencedImage::for_image(&image).unwrap();
+        assert_eq!(read.crs.unwrap().wkt, "LOCAL_CS[\"prj\"]");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
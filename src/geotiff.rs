@@ -0,0 +1,861 @@
+//! Reading & writing the georeferencing in GeoTIFF tags
+//!
+//! Only the first image (IFD) of a TIFF or BigTIFF file is used, and only the tags needed for
+//! the affine transform.
+use crate::{PixelAnchor, WorldFile, WorldFileError};
+use std::convert::TryFrom;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub(crate) const MODEL_PIXEL_SCALE_TAG: u16 = 33550;
+pub(crate) const MODEL_TIEPOINT_TAG: u16 = 33922;
+pub(crate) const MODEL_TRANSFORMATION_TAG: u16 = 34264;
+pub(crate) const GEO_KEY_DIRECTORY_TAG: u16 = 34735;
+
+pub(crate) const GT_RASTER_TYPE_GEO_KEY: u16 = 1025;
+pub(crate) const RASTER_PIXEL_IS_AREA: u16 = 1;
+pub(crate) const RASTER_PIXEL_IS_POINT: u16 = 2;
+
+pub(crate) const TYPE_SHORT: u16 = 3;
+pub(crate) const TYPE_DOUBLE: u16 = 12;
+
+/// Refuse to read tags with more values than this, rather than allocate huge amounts of memory
+/// for a corrupt file
+const MAX_VALUES: u64 = 1 << 20;
+
+/// The GeoTIFF tags for a [`WorldFile`]
+///
+/// Created with [`WorldFile::to_geotiff_tags`], for adding to a TIFF file with your own TIFF
+/// encoder. North-up images have a `ModelPixelScaleTag` & `ModelTiepointTag`, others have a
+/// `ModelTransformationTag`.
+///
+/// The `GeoKeyDirectoryTag` only has the `GTRasterTypeGeoKey` (PixelIsArea). Add keys for the CRS
+/// if you know it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoTiffTags {
+    /// `ModelPixelScaleTag` (DOUBLE)
+    pub model_pixel_scale: Option<[f64; 3]>,
+
+    /// `ModelTiepointTag` (DOUBLE)
+    pub model_tiepoint: Option<[f64; 6]>,
+
+    /// `ModelTransformationTag` (DOUBLE)
+    pub model_transformation: Option<[f64; 16]>,
+
+    /// `GeoKeyDirectoryTag` (SHORT)
+    pub geo_key_directory: Vec<u16>,
+}
+
+impl GeoTiffTags {
+    pub const MODEL_PIXEL_SCALE_TAG: u16 = MODEL_PIXEL_SCALE_TAG;
+    pub const MODEL_TIEPOINT_TAG: u16 = MODEL_TIEPOINT_TAG;
+    pub const MODEL_TRANSFORMATION_TAG: u16 = MODEL_TRANSFORMATION_TAG;
+    pub const GEO_KEY_DIRECTORY_TAG: u16 = GEO_KEY_DIRECTORY_TAG;
+}
+
+impl WorldFile {
+    /// The GeoTIFF tags which georeference an image like this world file does
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// let w = WorldFile::from_string("60\n0\n0\n-60\n440750\n3751290\n").unwrap();
+    /// let tags = w.to_geotiff_tags();
+    /// assert_eq!(tags.model_pixel_scale, Some([60., 60., 0.]));
+    /// assert_eq!(tags.model_tiepoint, Some([0., 0., 0., 440720., 3751320., 0.]));
+    /// assert_eq!(tags.model_transformation, None);
+    /// ```
+    pub fn to_geotiff_tags(&self) -> GeoTiffTags {
+        let (x, y) = self.origin(PixelAnchor::Corner);
+        let geo_key_directory = vec![
+            1,
+            1,
+            0,
+            1,
+            GT_RASTER_TYPE_GEO_KEY,
+            0,
+            1,
+            RASTER_PIXEL_IS_AREA,
+        ];
+        if self.x_skew == 0. && self.y_skew == 0. && self.x_scale > 0. && self.y_scale < 0. {
+            GeoTiffTags {
+                model_pixel_scale: Some([self.x_scale, -self.y_scale, 0.]),
+                model_tiepoint: Some([0., 0., 0., x, y, 0.]),
+                model_transformation: None,
+                geo_key_directory,
+            }
+        } else {
+            #[rustfmt::skip]
+            let matrix = [
+                self.x_scale, self.x_skew, 0., x,
+                self.y_skew, self.y_scale, 0., y,
+                0., 0., 0., 0.,
+                0., 0., 0., 1.,
+            ];
+            GeoTiffTags {
+                model_pixel_scale: None,
+                model_tiepoint: None,
+                model_transformation: Some(matrix),
+                geo_key_directory,
+            }
+        }
+    }
+
+    /// Add GeoTIFF tags to this TIFF file, in place, so it's georeferenced like this world file
+    ///
+    /// Any existing georeferencing tags in the first image are replaced. Other GeoKeys (e.g. for
+    /// the CRS) are kept. The new tags, and a new copy of the IFD, are appended to the file, and
+    /// the header is changed to point to the new IFD last, so if this fails part way through,
+    /// the file is still readable as before.
+    /// ```no_run
+    /// # use world_image_file::WorldFile;
+    /// let (w, _) = WorldFile::for_image("delivery.tif").unwrap();
+    /// w.write_geotiff_tags_path("delivery.tif").unwrap();
+    /// ```
+    pub fn write_geotiff_tags_path(&self, p: impl AsRef<Path>) -> Result<(), WorldFileError> {
+        let mut f = OpenOptions::new().read(true).write(true).open(p)?;
+        self.write_geotiff_tags(&mut f)?;
+        f.sync_all()?;
+        Ok(())
+    }
+
+    /// Add GeoTIFF tags to the TIFF file in this reader/writer, in place
+    ///
+    /// See [`write_geotiff_tags_path`](#method.write_geotiff_tags_path).
+    pub fn write_geotiff_tags(
+        &self,
+        mut f: impl Read + Write + Seek,
+    ) -> Result<(), WorldFileError> {
+        f.seek(SeekFrom::Start(0))?;
+        let tiff = TiffHeader::read(&mut f)?;
+        let mut entries = tiff.read_ifd(&mut f, tiff.first_ifd)?;
+        let mut next_ifd = vec![0; tiff.offset_size()];
+        f.read_exact(&mut next_ifd)
+            .map_err(|_| invalid("IFD is outside the file"))?;
+
+        let tags = self.to_geotiff_tags();
+        let existing_keys = match entries.iter().find(|e| e.tag == GEO_KEY_DIRECTORY_TAG) {
+            Some(entry) => Some(tiff.read_shorts(&mut f, entry)?),
+            None => None,
+        };
+        let geo_keys = match existing_keys {
+            Some(existing) => merge_geo_keys(&existing, &tags.geo_key_directory)?,
+            None => tags.geo_key_directory.clone(),
+        };
+
+        entries.retain(|e| {
+            ![
+                MODEL_PIXEL_SCALE_TAG,
+                MODEL_TIEPOINT_TAG,
+                MODEL_TRANSFORMATION_TAG,
+                GEO_KEY_DIRECTORY_TAG,
+            ]
+            .contains(&e.tag)
+        });
+
+        let bo = tiff.byte_order;
+        let doubles = |values: &[f64]| -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|v| bo.u64_bytes(v.to_bits()).to_vec())
+                .collect()
+        };
+        let mut new_tags = vec![(
+            GEO_KEY_DIRECTORY_TAG,
+            TYPE_SHORT,
+            geo_keys.len(),
+            geo_keys
+                .iter()
+                .flat_map(|&v| bo.u16_bytes(v).to_vec())
+                .collect::<Vec<u8>>(),
+        )];
+        if let Some(scale) = tags.model_pixel_scale {
+            new_tags.push((MODEL_PIXEL_SCALE_TAG, TYPE_DOUBLE, 3, doubles(&scale)));
+        }
+        if let Some(tiepoint) = tags.model_tiepoint {
+            new_tags.push((MODEL_TIEPOINT_TAG, TYPE_DOUBLE, 6, doubles(&tiepoint)));
+        }
+        if let Some(matrix) = tags.model_transformation {
+            new_tags.push((MODEL_TRANSFORMATION_TAG, TYPE_DOUBLE, 16, doubles(&matrix)));
+        }
+
+        // Append the values which don't fit in the IFD entries
+        let mut end = f.seek(SeekFrom::End(0))?;
+        for (tag, field_type, count, bytes) in new_tags {
+            let mut value = bytes;
+            if value.len() > tiff.offset_size() {
+                end = pad_to_word(&mut f, end)?;
+                f.write_all(&value)?;
+                let offset = end;
+                end += value.len() as u64;
+                value = tiff.offset_bytes(offset)?;
+            }
+            value.resize(tiff.offset_size(), 0);
+            entries.push(IfdEntry {
+                tag,
+                field_type,
+                count: count as u64,
+                value,
+            });
+        }
+        entries.sort_by_key(|e| e.tag);
+
+        // Append the new IFD
+        let ifd_offset = pad_to_word(&mut f, end)?;
+        let mut ifd = Vec::new();
+        if tiff.big_tiff {
+            ifd.extend_from_slice(&bo.u64_bytes(entries.len() as u64));
+        } else {
+            ifd.extend_from_slice(&bo.u16_bytes(entries.len() as u16));
+        }
+        for entry in &entries {
+            ifd.extend_from_slice(&bo.u16_bytes(entry.tag));
+            ifd.extend_from_slice(&bo.u16_bytes(entry.field_type));
+            ifd.extend(tiff.offset_bytes(entry.count)?);
+            ifd.extend_from_slice(&entry.value);
+        }
+        ifd.extend(next_ifd);
+        f.write_all(&ifd)?;
+        f.flush()?;
+
+        // Finally point the header at the new IFD
+        let header_offset = if tiff.big_tiff { 8 } else { 4 };
+        f.seek(SeekFrom::Start(header_offset))?;
+        f.write_all(&tiff.offset_bytes(ifd_offset)?)?;
+        f.flush()?;
+        Ok(())
+    }
+
+    /// Read the georeferencing from the GeoTIFF tags of this TIFF file
+    ///
+    /// Either the `ModelTransformationTag`, or the `ModelPixelScaleTag` and first
+    /// `ModelTiepointTag`, is used. `GTRasterTypeGeoKey` is respected, so the result is the same
+    /// for PixelIsArea and PixelIsPoint files.
+    /// ```no_run
+    /// # use world_image_file::{WorldFile, SidecarStyle};
+    /// let w = WorldFile::from_geotiff_path("map.tif").unwrap();
+    /// w.write_for_image("map.tif", SidecarStyle::ThreeLetter, None).unwrap();
+    /// ```
+    pub fn from_geotiff_path(p: impl AsRef<Path>) -> Result<Self, WorldFileError> {
+        let f = File::open(p)?;
+        Self::from_geotiff_reader(BufReader::new(f))
+    }
+
+    /// Read the georeferencing from the GeoTIFF tags of a TIFF file in this reader
+    ///
+    /// See [`from_geotiff_path`](#method.from_geotiff_path).
+    pub fn from_geotiff_reader(mut r: impl Read + Seek) -> Result<Self, WorldFileError> {
+        r.seek(SeekFrom::Start(0))?;
+        let tiff = TiffHeader::read(&mut r)?;
+        let entries = tiff.read_ifd(&mut r, tiff.first_ifd)?;
+        let find = |tag| entries.iter().find(|e| e.tag == tag);
+
+        let anchor = match find(GEO_KEY_DIRECTORY_TAG) {
+            Some(entry) => raster_type(&tiff.read_shorts(&mut r, entry)?)?,
+            None => PixelAnchor::Corner,
+        };
+
+        if let Some(entry) = find(MODEL_TRANSFORMATION_TAG) {
+            let m = tiff.read_doubles(&mut r, entry)?;
+            if m.len() != 16 {
+                return Err(invalid("ModelTransformationTag does not have 16 values"));
+            }
+            return WorldFile::from_origin(m[0], m[4], m[1], m[5], (m[3], m[7]), anchor);
+        }
+
+        match (find(MODEL_PIXEL_SCALE_TAG), find(MODEL_TIEPOINT_TAG)) {
+            (Some(scale), Some(tiepoint)) => {
+                let scale = tiff.read_doubles(&mut r, scale)?;
+                let tiepoint = tiff.read_doubles(&mut r, tiepoint)?;
+                if scale.len() < 2 || tiepoint.len() < 6 {
+                    return Err(invalid(
+                        "ModelPixelScaleTag or ModelTiepointTag is too short",
+                    ));
+                }
+                let (sx, sy) = (scale[0], scale[1]);
+                let (i, j, x, y) = (tiepoint[0], tiepoint[1], tiepoint[3], tiepoint[4]);
+                // Raster coordinate (0, 0), from the tiepoint at raster coordinate (i, j)
+                let origin = (x - i * sx, y + j * sy);
+                WorldFile::from_origin(sx, 0., 0., -sy, origin, anchor)
+            }
+            _ => Err(WorldFileError::NotGeoreferenced),
+        }
+    }
+}
+
+fn invalid(msg: &str) -> WorldFileError {
+    WorldFileError::InvalidTiff(msg.to_string())
+}
+
+/// What raster coordinate (0, 0) is, from the `GeoKeyDirectoryTag` values
+fn raster_type(directory: &[u16]) -> Result<PixelAnchor, WorldFileError> {
+    if directory.len() < 4 {
+        return Err(invalid("GeoKeyDirectoryTag is too short"));
+    }
+    let num_keys = usize::from(directory[3]);
+    for key in directory[4..].chunks_exact(4).take(num_keys) {
+        // key: [KeyID, TIFFTagLocation, Count, Value_Offset]
+        if key[0] == GT_RASTER_TYPE_GEO_KEY && key[1] == 0 {
+            return match key[3] {
+                RASTER_PIXEL_IS_AREA => Ok(PixelAnchor::Corner),
+                RASTER_PIXEL_IS_POINT => Ok(PixelAnchor::Center),
+                _ => Err(invalid("unknown GTRasterTypeGeoKey")),
+            };
+        }
+    }
+    Ok(PixelAnchor::Corner)
+}
+
+/// Add (or replace) the keys in `new` to the `existing` GeoKeyDirectoryTag values
+///
+/// Only keys with their values in the directory (TIFFTagLocation 0) can be added.
+fn merge_geo_keys(existing: &[u16], new: &[u16]) -> Result<Vec<u16>, WorldFileError> {
+    if existing.len() < 4 || new.len() < 4 {
+        return Err(invalid("GeoKeyDirectoryTag is too short"));
+    }
+    let keys = |directory: &[u16]| -> Vec<[u16; 4]> {
+        directory[4..]
+            .chunks_exact(4)
+            .take(usize::from(directory[3]))
+            .map(|k| [k[0], k[1], k[2], k[3]])
+            .collect()
+    };
+    let new_keys = keys(new);
+    let mut merged = keys(existing);
+    merged.retain(|k| !new_keys.iter().any(|n| n[0] == k[0]));
+    merged.extend(new_keys);
+    // Keys must be sorted by KeyID
+    merged.sort_by_key(|k| k[0]);
+
+    let mut directory = existing[..3].to_vec();
+    directory.push(merged.len() as u16);
+    directory.extend(merged.iter().flatten());
+    Ok(directory)
+}
+
+/// TIFF data must start on a word boundary, so write a padding byte at `end` if it's odd
+fn pad_to_word(f: &mut impl Write, end: u64) -> Result<u64, WorldFileError> {
+    if end % 2 == 1 {
+        f.write_all(&[0])?;
+        Ok(end + 1)
+    } else {
+        Ok(end)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    pub(crate) fn u16(self, b: [u8; 2]) -> u16 {
+        match self {
+            ByteOrder::Little => u16::from_le_bytes(b),
+            ByteOrder::Big => u16::from_be_bytes(b),
+        }
+    }
+
+    pub(crate) fn u32(self, b: [u8; 4]) -> u32 {
+        match self {
+            ByteOrder::Little => u32::from_le_bytes(b),
+            ByteOrder::Big => u32::from_be_bytes(b),
+        }
+    }
+
+    pub(crate) fn u64(self, b: [u8; 8]) -> u64 {
+        match self {
+            ByteOrder::Little => u64::from_le_bytes(b),
+            ByteOrder::Big => u64::from_be_bytes(b),
+        }
+    }
+
+    pub(crate) fn u16_bytes(self, v: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::Little => v.to_le_bytes(),
+            ByteOrder::Big => v.to_be_bytes(),
+        }
+    }
+
+    pub(crate) fn u32_bytes(self, v: u32) -> [u8; 4] {
+        match self {
+            ByteOrder::Little => v.to_le_bytes(),
+            ByteOrder::Big => v.to_be_bytes(),
+        }
+    }
+
+    pub(crate) fn u64_bytes(self, v: u64) -> [u8; 8] {
+        match self {
+            ByteOrder::Little => v.to_le_bytes(),
+            ByteOrder::Big => v.to_be_bytes(),
+        }
+    }
+}
+
+/// One tag of an IFD, with its value not yet read
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct IfdEntry {
+    pub(crate) tag: u16,
+    pub(crate) field_type: u16,
+    pub(crate) count: u64,
+    /// The value, if it fits, otherwise the offset of the value. 4 bytes for TIFF, 8 for
+    /// BigTIFF.
+    pub(crate) value: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct TiffHeader {
+    pub(crate) byte_order: ByteOrder,
+    pub(crate) big_tiff: bool,
+    pub(crate) first_ifd: u64,
+}
+
+impl TiffHeader {
+    pub(crate) fn read(r: &mut impl Read) -> Result<Self, WorldFileError> {
+        let mut header = [0; 8];
+        r.read_exact(&mut header)
+            .map_err(|_| invalid("file too short"))?;
+        let byte_order = match &header[0..2] {
+            b"II" => ByteOrder::Little,
+            b"MM" => ByteOrder::Big,
+            _ => return Err(invalid("not a TIFF file")),
+        };
+        match byte_order.u16([header[2], header[3]]) {
+            42 => Ok(TiffHeader {
+                byte_order,
+                big_tiff: false,
+                first_ifd: u64::from(byte_order.u32([header[4], header[5], header[6], header[7]])),
+            }),
+            43 => {
+                let mut offset = [0; 8];
+                r.read_exact(&mut offset)
+                    .map_err(|_| invalid("file too short"))?;
+                Ok(TiffHeader {
+                    byte_order,
+                    big_tiff: true,
+                    first_ifd: byte_order.u64(offset),
+                })
+            }
+            _ => Err(invalid("not a TIFF file")),
+        }
+    }
+
+    /// Size in bytes of an offset, and of the inline value of an IFD entry
+    pub(crate) fn offset_size(&self) -> usize {
+        if self.big_tiff {
+            8
+        } else {
+            4
+        }
+    }
+
+    /// Read an offset (or count) which is 4 bytes in TIFF, and 8 in BigTIFF
+    pub(crate) fn offset(&self, b: &[u8]) -> u64 {
+        if self.big_tiff {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&b[..8]);
+            self.byte_order.u64(bytes)
+        } else {
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(&b[..4]);
+            u64::from(self.byte_order.u32(bytes))
+        }
+    }
+
+    /// Write an offset (or count), 4 bytes in TIFF, and 8 in BigTIFF
+    pub(crate) fn offset_bytes(&self, offset: u64) -> Result<Vec<u8>, WorldFileError> {
+        if self.big_tiff {
+            Ok(self.byte_order.u64_bytes(offset).to_vec())
+        } else {
+            let offset = u32::try_from(offset)
+                .map_err(|_| invalid("file is too large for TIFF, it needs to be a BigTIFF"))?;
+            Ok(self.byte_order.u32_bytes(offset).to_vec())
+        }
+    }
+
+    /// Read all the entries of the IFD at this offset
+    pub(crate) fn read_ifd(
+        &self,
+        r: &mut (impl Read + Seek),
+        offset: u64,
+    ) -> Result<Vec<IfdEntry>, WorldFileError> {
+        let count_size = if self.big_tiff { 8 } else { 2 };
+        let mut count = [0; 8];
+        r.seek(SeekFrom::Start(offset))?;
+        r.read_exact(&mut count[..count_size])
+            .map_err(|_| invalid("IFD is outside the file"))?;
+        let count = if self.big_tiff {
+            self.offset(&count)
+        } else {
+            u64::from(self.byte_order.u16([count[0], count[1]]))
+        };
+        if count > u64::from(u16::MAX) {
+            return Err(invalid("IFD has too many entries"));
+        }
+
+        let entry_size = 4 + self.offset_size() * 2;
+        let mut entries = Vec::with_capacity(count as usize);
+        let mut buf = vec![0; entry_size];
+        for _ in 0..count {
+            r.read_exact(&mut buf)
+                .map_err(|_| invalid("IFD is outside the file"))?;
+            entries.push(IfdEntry {
+                tag: self.byte_order.u16([buf[0], buf[1]]),
+                field_type: self.byte_order.u16([buf[2], buf[3]]),
+                count: self.offset(&buf[4..]),
+                value: buf[4 + self.offset_size()..].to_vec(),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// The raw bytes of this entry's values, which are each `size` bytes
+    fn read_bytes(
+        &self,
+        r: &mut (impl Read + Seek),
+        entry: &IfdEntry,
+        size: u64,
+    ) -> Result<Vec<u8>, WorldFileError> {
+        if entry.count > MAX_VALUES {
+            return Err(invalid("tag has too many values"));
+        }
+        let len = (entry.count * size) as usize;
+        if len <= entry.value.len() {
+            return Ok(entry.value[..len].to_vec());
+        }
+        let mut bytes = vec![0; len];
+        r.seek(SeekFrom::Start(self.offset(&entry.value)))?;
+        r.read_exact(&mut bytes)
+            .map_err(|_| invalid("tag value is outside the file"))?;
+        Ok(bytes)
+    }
+
+    pub(crate) fn read_doubles(
+        &self,
+        r: &mut (impl Read + Seek),
+        entry: &IfdEntry,
+    ) -> Result<Vec<f64>, WorldFileError> {
+        if entry.field_type != TYPE_DOUBLE {
+            return Err(invalid("expected a DOUBLE tag"));
+        }
+        let bytes = self.read_bytes(r, entry, 8)?;
+        Ok(bytes
+            .chunks_exact(8)
+            .map(|b| {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(b);
+                f64::from_bits(self.byte_order.u64(bytes))
+            })
+            .collect())
+    }
+
+    pub(crate) fn read_shorts(
+        &self,
+        r: &mut (impl Read + Seek),
+        entry: &IfdEntry,
+    ) -> Result<Vec<u16>, WorldFileError> {
+        if entry.field_type != TYPE_SHORT {
+            return Err(invalid("expected a SHORT tag"));
+        }
+        let bytes = self.read_bytes(r, entry, 2)?;
+        Ok(bytes
+            .chunks_exact(2)
+            .map(|b| self.byte_order.u16([b[0], b[1]]))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    enum Value {
+        Shorts(Vec<u16>),
+        Doubles(Vec<f64>),
+    }
+
+    /// A TIFF file with one IFD with these tags, and no image data
+    fn tiff(byte_order: ByteOrder, big_tiff: bool, tags: &[(u16, Value)]) -> Vec<u8> {
+        let header = TiffHeader {
+            byte_order,
+            big_tiff,
+            first_ifd: if big_tiff { 16 } else { 8 },
+        };
+        let u16_bytes = |v: u16| byte_order.u16_bytes(v);
+        let offset_bytes = |v: u64| header.offset_bytes(v).unwrap();
+        let offset_size = header.offset_size();
+
+        let mut out = match byte_order {
+            ByteOrder::Little => b"II".to_vec(),
+            ByteOrder::Big => b"MM".to_vec(),
+        };
+        if big_tiff {
+            out.extend(u16_bytes(43));
+            out.extend(u16_bytes(8));
+            out.extend(u16_bytes(0));
+            out.extend(offset_bytes(header.first_ifd));
+            out.extend(offset_bytes(tags.len() as u64));
+        } else {
+            out.extend(u16_bytes(42));
+            out.extend(offset_bytes(header.first_ifd));
+            out.extend(u16_bytes(tags.len() as u16));
+        }
+
+        let ifd_end = out.len() + tags.len() * (4 + 2 * offset_size) + offset_size;
+        let mut data = Vec::new();
+        for (tag, value) in tags {
+            let (field_type, count, bytes) = match value {
+                Value::Shorts(v) => (
+                    TYPE_SHORT,
+                    v.len(),
+                    v.iter().flat_map(|&v| u16_bytes(v)).collect::<Vec<_>>(),
+                ),
+                Value::Doubles(v) => (
+                    TYPE_DOUBLE,
+                    v.len(),
+                    v.iter()
+                        .flat_map(|v| byte_order.u64_bytes(v.to_bits()))
+                        .collect(),
+                ),
+            };
+            out.extend(u16_bytes(*tag));
+            out.extend(u16_bytes(field_type));
+            out.extend(offset_bytes(count as u64));
+            if bytes.len() <= offset_size {
+                let mut inline = bytes.clone();
+                inline.resize(offset_size, 0);
+                out.extend(inline);
+            } else {
+                out.extend(offset_bytes((ifd_end + data.len()) as u64));
+                data.extend(bytes);
+            }
+        }
+        out.extend(offset_bytes(0));
+        out.extend(data);
+        out
+    }
+
+    fn geo_keys(raster_type: u16) -> Value {
+        Value::Shorts(vec![
+            1,
+            1,
+            0,
+            2,
+            1024,
+            0,
+            1,
+            1,
+            GT_RASTER_TYPE_GEO_KEY,
+            0,
+            1,
+            raster_type,
+        ])
+    }
+
+    fn read(bytes: Vec<u8>) -> Result<WorldFile, WorldFileError> {
+        WorldFile::from_geotiff_reader(Cursor::new(bytes))
+    }
+
+    #[test]
+    fn test_scale_tiepoint() {
+        let expected = WorldFile::new(60., 0., 0., -60., 440750., 3751290.).unwrap();
+        for &byte_order in &[ByteOrder::Little, ByteOrder::Big] {
+            for &big_tiff in &[false, true] {
+                let tags = [
+                    (MODEL_PIXEL_SCALE_TAG, Value::Doubles(vec![60., 60., 0.])),
+                    (
+                        MODEL_TIEPOINT_TAG,
+                        Value::Doubles(vec![0., 0., 0., 440720., 3751320., 0.]),
+                    ),
+                    (GEO_KEY_DIRECTORY_TAG, geo_keys(RASTER_PIXEL_IS_AREA)),
+                ];
+                assert_eq!(read(tiff(byte_order, big_tiff, &tags)).unwrap(), expected);
+            }
+        }
+
+        // Tiepoint not at the origin, and no GeoKeyDirectoryTag
+        let tags = [
+            (MODEL_PIXEL_SCALE_TAG, Value::Doubles(vec![60., 60., 0.])),
+            (
+                MODEL_TIEPOINT_TAG,
+                Value::Doubles(vec![10., 20., 0., 441320., 3750120., 0.]),
+            ),
+        ];
+        assert_eq!(
+            read(tiff(ByteOrder::Little, false, &tags)).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_pixel_is_point() {
+        let tags = [
+            (MODEL_PIXEL_SCALE_TAG, Value::Doubles(vec![60., 60., 0.])),
+            (
+                MODEL_TIEPOINT_TAG,
+                Value::Doubles(vec![0., 0., 0., 440750., 3751290., 0.]),
+            ),
+            (GEO_KEY_DIRECTORY_TAG, geo_keys(RASTER_PIXEL_IS_POINT)),
+        ];
+        assert_eq!(
+            read(tiff(ByteOrder::Big, false, &tags)).unwrap(),
+            WorldFile::new(60., 0., 0., -60., 440750., 3751290.).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_transformation() {
+        #[rustfmt::skip]
+        let matrix = vec![
+            3., 4., 0., 1000.,
+            4., -3., 0., 2000.,
+            0., 0., 0., 0.,
+            0., 0., 0., 1.,
+        ];
+        let tags = [
+            (MODEL_TRANSFORMATION_TAG, Value::Doubles(matrix)),
+            (GEO_KEY_DIRECTORY_TAG, geo_keys(RASTER_PIXEL_IS_AREA)),
+        ];
+        assert_eq!(
+            read(tiff(ByteOrder::Little, true, &tags)).unwrap(),
+            WorldFile::from_gdal_geotransform([1000., 3., 4., 2000., 4., -3.]).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_to_geotiff_tags() {
+        let w = WorldFile::from_gdal_geotransform([1000., 3., 4., 2000., 4., -3.]).unwrap();
+        let tags = w.to_geotiff_tags();
+        assert_eq!(tags.model_pixel_scale, None);
+        assert_eq!(tags.model_tiepoint, None);
+        let matrix = tags.model_transformation.unwrap();
+        let tags = [
+            (MODEL_TRANSFORMATION_TAG, Value::Doubles(matrix.to_vec())),
+            (GEO_KEY_DIRECTORY_TAG, Value::Shorts(tags.geo_key_directory)),
+        ];
+        assert_eq!(read(tiff(ByteOrder::Little, false, &tags)).unwrap(), w);
+
+        // South-up images can't use ModelPixelScaleTag
+        let w = WorldFile::new(60., 0., 0., 60., 440750., 3751290.).unwrap();
+        assert!(w.to_geotiff_tags().model_transformation.is_some());
+    }
+
+    #[test]
+    fn test_write_geotiff_tags() {
+        let north_up = WorldFile::new(60., 0., 0., -60., 440750., 3751290.).unwrap();
+        let rotated = WorldFile::from_gdal_geotransform([1000., 3., 4., 2000., 4., -3.]).unwrap();
+        for &byte_order in &[ByteOrder::Little, ByteOrder::Big] {
+            for &big_tiff in &[false, true] {
+                // An ImageWidth tag, and a PixelIsPoint file with a CRS
+                let tags = [
+                    (256, Value::Shorts(vec![100])),
+                    (MODEL_PIXEL_SCALE_TAG, Value::Doubles(vec![1., 1., 0.])),
+                    (
+                        MODEL_TIEPOINT_TAG,
+                        Value::Doubles(vec![0., 0., 0., 0., 0., 0.]),
+                    ),
+                    (
+                        GEO_KEY_DIRECTORY_TAG,
+                        Value::Shorts(vec![
+                            1,
+                            1,
+                            0,
+                            2,
+                            GT_RASTER_TYPE_GEO_KEY,
+                            0,
+                            1,
+                            RASTER_PIXEL_IS_POINT,
+                            3072,
+                            0,
+                            1,
+                            32611,
+                        ]),
+                    ),
+                ];
+                let mut file = Cursor::new(tiff(byte_order, big_tiff, &tags));
+
+                for w in &[rotated, north_up] {
+                    w.write_geotiff_tags(&mut file).unwrap();
+                    assert_eq!(&WorldFile::from_geotiff_reader(&mut file).unwrap(), w);
+
+                    file.set_position(0);
+                    let header = TiffHeader::read(&mut file).unwrap();
+                    let entries = header.read_ifd(&mut file, header.first_ifd).unwrap();
+                    let tags = entries.iter().map(|e| e.tag).collect::<Vec<_>>();
+                    assert_eq!(tags[0], 256);
+                    assert_eq!(&entries[0].value[..2], &byte_order.u16_bytes(100));
+                    if w == &north_up {
+                        assert!(!tags.contains(&MODEL_TRANSFORMATION_TAG));
+                    } else {
+                        assert!(!tags.contains(&MODEL_TIEPOINT_TAG));
+                    }
+
+                    let geo_keys = entries.iter().find(|e| e.tag == GEO_KEY_DIRECTORY_TAG);
+                    let geo_keys = header.read_shorts(&mut file, geo_keys.unwrap()).unwrap();
+                    assert_eq!(
+                        geo_keys,
+                        vec![
+                            1,
+                            1,
+                            0,
+                            2,
+                            1025,
+                            0,
+                            1,
+                            RASTER_PIXEL_IS_AREA,
+                            3072,
+                            0,
+                            1,
+                            32611
+                        ]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_geotiff_tags_path() {
+        let dir = crate::test_util::test_dir("write_geotiff_tags");
+        let path = dir.join("map.tif");
+        std::fs::write(
+            &path,
+            tiff(ByteOrder::Little, false, &[(256, Value::Shorts(vec![100]))]),
+        )
+        .unwrap();
+        assert!(matches!(
+            WorldFile::from_geotiff_path(&path),
+            Err(WorldFileError::NotGeoreferenced)
+        ));
+
+        let w = WorldFile::new(60., 0., 0., -60., 440750., 3751290.).unwrap();
+        w.write_geotiff_tags_path(&path).unwrap();
+        assert_eq!(WorldFile::from_geotiff_path(&path).unwrap(), w);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            read(b"not a tiff".to_vec()),
+            Err(WorldFileError::InvalidTiff(_))
+        ));
+        assert!(matches!(
+            read(tiff(ByteOrder::Little, false, &[])),
+            Err(WorldFileError::NotGeoreferenced)
+        ));
+        let tags = [(
+            MODEL_TIEPOINT_TAG,
+            Value::Doubles(vec![0., 0., 0., 1., 2., 0.]),
+        )];
+        assert!(matches!(
+            read(tiff(ByteOrder::Little, false, &tags)),
+            Err(WorldFileError::NotGeoreferenced)
+        ));
+        let tags = [(MODEL_PIXEL_SCALE_TAG, Value::Shorts(vec![1, 1, 0]))];
+        let mut bytes = tiff(ByteOrder::Little, false, &tags);
+        bytes.truncate(12);
+        assert!(matches!(read(bytes), Err(WorldFileError::InvalidTiff(_))));
+    }
+}
@@ -19,24 +19,79 @@
 //! assert_eq!(w.image_to_world((171., 343.)), (696672., 4565024.));
 //! assert_eq!(w.world_to_image((696672., 4565024.)), (171., 343.));
 //! ````
-//! Pixel coordinates can be fractional. `(10.0, 2.0)` refers to the top left of pixel (10, 2).
-//! `(10.5, 2.5)` is in the middle of pixel (10, 2).
+//! Pixel coordinates can be fractional. As in the world file format, `(10.0, 2.0)` refers to the
+//! centre of pixel (10, 2), and `(9.5, 1.5)` is the top left corner of pixel (10, 2). Use
+//! [`PixelAnchor::Corner`] with `image_to_world_with`/`world_to_image_with` for coordinates
+//! where `(10.0, 2.0)` is the top left corner of pixel (10, 2).
 //!
 //! World Files do not store any SRID/spatial reference system (SRS)/coordinate reference system
 //! (CRS) data. World Files were originally defined by
 //! [ESRI](https://support.esri.com/en/technical-article/000002860).
+//! The CRS is conventionally in a `.prj` file next to the world file, which
+//! [`GeoreferencedImage`] reads & writes along with the world file.
 //!
-//! Currently `Result<WorldFile, ()>` is returned (i.e. all errors are flatted to `()`), but this
-//! may change to be more descriptive, and would not be seen as a breaking change.
+//! Errors are returned as a [`WorldFileError`], which says what went wrong, and on which line.
+//!
+//! The world file for an image (e.g. `map.jgw` or `map.wld` for `map.jpg`) can be found, and
+//! read, with `WorldFile::for_image(&image_path)`.
+//!
+//! By default parsing is lenient, accepting common quirks like whitespace or byte order marks.
+//! Use [`ParseOptions`] with `WorldFile::from_path_with` (or `from_string_with`) to be stricter,
+//! or more lenient, and to see what was accepted.
+//!
+//! A world file can be fitted to ground control points (GCPs), e.g. from clicking on a scanned
+//! map, with `WorldFile::from_gcps(&gcps)`, or with `WorldFile::from_gcps_robust` to find and
+//! ignore mis-clicks. `WorldFile::fit_gcps` fits constrained transforms, e.g. north-up only.
+//!
+//! World files are affine transforms, and can be composed with `*` (`a * b` applies `b` first),
+//! inverted with `inverse()`, and built up with `translate`, `scale`, and `rotate`.
+//! The world file for a resized or cropped image is given by `resized`, `scaled_by`, `cropped`
+//! and `windowed`, and for a rotated or mirrored image (e.g. to fix its EXIF orientation) by
+//! `oriented`.
+use std::ffi::OsString;
 use std::fmt;
-use std::fs::File;
-use std::io::{Read, Write};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+mod error;
+pub use error::WorldFileError;
+
+mod parse;
+pub use parse::{Diagnostic, ParseOptions};
+
+mod write;
+pub use write::WriteOptions;
+
+mod sidecar;
+pub use sidecar::SidecarStyle;
+
+mod extent;
+pub use extent::Bounds;
+
+mod georeferenced;
+pub use georeferenced::{Crs, GeoreferencedImage};
+
+mod affine;
+mod components;
+mod gcp;
+pub use gcp::{Gcp, GcpFit, GcpModel, RobustGcpFit, RobustOptions};
+
+mod gdal;
+mod geotiff;
+pub use geotiff::GeoTiffTags;
+mod orientation;
+pub use orientation::Orientation;
+mod pam;
+mod resize;
+mod window;
+pub use window::Window;
 
 /// A World File
 ///
 /// See the [module top level documention](./index.html)
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct WorldFile {
     pub x_scale: f64,
     pub y_scale: f64,
@@ -49,68 +104,186 @@ pub struct WorldFile {
 }
 
 impl WorldFile {
+    /// Create a world file from its six values, in the same order as the lines of the file
+    ///
+    /// Returns an error if any value is not finite, or if the transform cannot be inverted.
+    pub fn new(
+        x_scale: f64,
+        y_skew: f64,
+        x_skew: f64,
+        y_scale: f64,
+        x_coord: f64,
+        y_coord: f64,
+    ) -> Result<Self, WorldFileError> {
+        let values = [x_scale, y_skew, x_skew, y_scale, x_coord, y_coord];
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(WorldFileError::NonFinite { line: None });
+        }
+        let w = WorldFile {
+            x_scale,
+            y_scale,
+            x_skew,
+            y_skew,
+            x_coord,
+            y_coord,
+        };
+        if w.determinant() == 0. {
+            return Err(WorldFileError::ZeroScale);
+        }
+        Ok(w)
+    }
+
     /// Open a world file from a path
-    pub fn from_path(p: impl AsRef<Path>) -> Result<Self, ()> {
+    pub fn from_path(p: impl AsRef<Path>) -> Result<Self, WorldFileError> {
         let p: &Path = p.as_ref();
-        let mut f = File::open(p).or(Err(()))?;
+        let mut f = File::open(p)?;
         Self::from_reader(&mut f)
     }
 
     /// Open a world file from something that can `Read`
-    pub fn from_reader(mut r: impl Read) -> Result<Self, ()> {
+    pub fn from_reader(mut r: impl Read) -> Result<Self, WorldFileError> {
         let mut s = String::new();
-        r.read_to_string(&mut s).or(Err(()))?;
+        r.read_to_string(&mut s)?;
         Self::from_string(&s)
     }
 
     /// Open a world file from a raw string file content
-    pub fn from_string(s: impl AsRef<str>) -> Result<Self, ()> {
-        let s: &str = s.as_ref();
-        let lines: Vec<&str> = s.lines().collect();
-        let x_scale = lines.get(0).and_then(|s| s.parse().ok()).ok_or(())?;
-        let y_skew = lines.get(1).and_then(|s| s.parse().ok()).ok_or(())?;
-        let x_skew = lines.get(2).and_then(|s| s.parse().ok()).ok_or(())?;
-        let y_scale = lines.get(3).and_then(|s| s.parse().ok()).ok_or(())?;
-        let x_coord = lines.get(4).and_then(|s| s.parse().ok()).ok_or(())?;
-        let y_coord = lines.get(5).and_then(|s| s.parse().ok()).ok_or(())?;
-        assert!(x_scale != 0.);
-        assert!(y_scale != 0.);
+    ///
+    /// This uses the default, [lenient](ParseOptions::lenient), parsing options.
+    pub fn from_string(s: impl AsRef<str>) -> Result<Self, WorldFileError> {
+        Self::from_string_with(s, &ParseOptions::default()).map(|(w, _)| w)
+    }
 
-        Ok(WorldFile {
-            x_scale,
-            y_scale,
-            x_skew,
-            y_skew,
-            x_coord,
-            y_coord,
-        })
+    /// Open a world file from a path, with these parsing options
+    ///
+    /// Returns the world file, and everything that was tolerated while parsing it.
+    pub fn from_path_with(
+        p: impl AsRef<Path>,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<Diagnostic>), WorldFileError> {
+        let p: &Path = p.as_ref();
+        let mut f = File::open(p)?;
+        Self::from_reader_with(&mut f, options)
     }
 
-    /// Convert this world file to a raw string
-    pub fn to_string(&self) -> String {
-        format!(
-            "{}\n{}\n{}\n{}\n{}\n{}\n",
-            self.x_scale, self.y_skew, self.x_skew, self.y_scale, self.x_coord, self.y_coord
-        )
+    /// Open a world file from something that can `Read`, with these parsing options
+    ///
+    /// Returns the world file, and everything that was tolerated while parsing it.
+    pub fn from_reader_with(
+        mut r: impl Read,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<Diagnostic>), WorldFileError> {
+        let mut s = String::new();
+        r.read_to_string(&mut s)?;
+        Self::from_string_with(&s, options)
+    }
+
+    /// Open a world file from a raw string file content, with these parsing options
+    ///
+    /// Returns the world file, and everything that was tolerated while parsing it.
+    pub fn from_string_with(
+        s: impl AsRef<str>,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<Diagnostic>), WorldFileError> {
+        parse::parse(s.as_ref(), options)
+    }
+
+    /// Convert this world file to a raw string, with these formatting options
+    ///
+    /// `to_string()` uses the default options.
+    pub fn to_string_with(&self, options: &WriteOptions) -> String {
+        let mut s = String::new();
+        write::format(self, options, &mut s).expect("writing to a String cannot fail");
+        s
     }
 
     /// Write this world file to this `Write` object
-    pub fn write_to_writer(&self, mut w: impl Write) {
-        write!(
-            w,
-            "{}\n{}\n{}\n{}\n{}\n{}\n",
-            self.x_scale, self.y_skew, self.x_skew, self.y_scale, self.x_coord, self.y_coord
-        )
-        .unwrap();
+    pub fn write_to_writer(&self, w: impl Write) -> Result<(), WorldFileError> {
+        self.write_to_writer_with(w, &WriteOptions::default())
+    }
+
+    /// Write this world file to this `Write` object, with these formatting options
+    pub fn write_to_writer_with(
+        &self,
+        mut w: impl Write,
+        options: &WriteOptions,
+    ) -> Result<(), WorldFileError> {
+        w.write_all(self.to_string_with(options).as_bytes())?;
+        Ok(())
     }
 
     /// Write this world file to a path
-    pub fn write_to_path(&self, p: impl AsRef<Path>) {
-        let mut f = File::create(p).unwrap();
-        self.write_to_writer(&mut f);
+    pub fn write_to_path(&self, p: impl AsRef<Path>) -> Result<(), WorldFileError> {
+        self.write_to_path_with(p, &WriteOptions::default())
+    }
+
+    /// Write this world file to a path, with these formatting options
+    pub fn write_to_path_with(
+        &self,
+        p: impl AsRef<Path>,
+        options: &WriteOptions,
+    ) -> Result<(), WorldFileError> {
+        let mut f = File::create(p)?;
+        self.write_to_writer_with(&mut f, options)
+    }
+
+    /// Write this world file to a path atomically
+    ///
+    /// The file is written to a temporary file in the same directory, which is then renamed to
+    /// `p`. Readers will see either the old file, or the complete new file, never a partly
+    /// written one.
+    pub fn write_to_path_atomic(&self, p: impl AsRef<Path>) -> Result<(), WorldFileError> {
+        self.write_to_path_atomic_with(p, &WriteOptions::default())
+    }
+
+    /// Write this world file to a path atomically, with these formatting options
+    ///
+    /// See [`write_to_path_atomic`](#method.write_to_path_atomic).
+    pub fn write_to_path_atomic_with(
+        &self,
+        p: impl AsRef<Path>,
+        options: &WriteOptions,
+    ) -> Result<(), WorldFileError> {
+        write_atomic(p.as_ref(), self.to_string_with(options).as_bytes())
+    }
+
+    /// Create a world file from its scale & skew values, and the world coordinates of the top
+    /// left pixel
+    ///
+    /// `origin` is the top left corner of the image, or the centre of the top left pixel,
+    /// depending on `anchor`.
+    /// ```
+    /// # use world_image_file::{WorldFile, PixelAnchor};
+    /// let w = WorldFile::from_origin(32., 0., 0., -32., (691184., 4576016.), PixelAnchor::Corner).unwrap();
+    /// assert_eq!((w.x_coord, w.y_coord), (691200., 4576000.));
+    /// ```
+    pub fn from_origin(
+        x_scale: f64,
+        y_skew: f64,
+        x_skew: f64,
+        y_scale: f64,
+        origin: (f64, f64),
+        anchor: PixelAnchor,
+    ) -> Result<Self, WorldFileError> {
+        let mut w = Self::new(x_scale, y_skew, x_skew, y_scale, origin.0, origin.1)?;
+        if anchor == PixelAnchor::Corner {
+            // The origin is half a pixel up & left of the top left pixel's centre
+            let (x_coord, y_coord) = w.image_to_world((0.5, 0.5));
+            w.x_coord = x_coord;
+            w.y_coord = y_coord;
+        }
+        Ok(w)
+    }
+
+    /// The world coordinates of the top left corner of the image, or the centre of the top left
+    /// pixel, depending on `anchor`
+    pub fn origin(&self, anchor: PixelAnchor) -> (f64, f64) {
+        self.image_to_world_with((0., 0.), anchor)
     }
 
     /// Convert image coordinates to world coordinates.
+    ///
+    /// Integer image coordinates are the centres of pixels, see [`PixelAnchor::Center`].
     pub fn image_to_world(&self, image_x_y: impl Into<(f64, f64)>) -> (f64, f64) {
         let x_y = image_x_y.into();
         let x = x_y.0;
@@ -122,46 +295,438 @@ impl WorldFile {
         )
     }
 
+    /// Convert image coordinates, anchored at `anchor`, to world coordinates.
+    /// ```
+    /// # use world_image_file::{WorldFile, PixelAnchor};
+    /// # let w = WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n").unwrap();
+    /// assert_eq!(w.image_to_world_with((0., 0.), PixelAnchor::Center), (691200., 4576000.));
+    /// assert_eq!(w.image_to_world_with((0., 0.), PixelAnchor::Corner), (691184., 4576016.));
+    /// ```
+    pub fn image_to_world_with(
+        &self,
+        image_x_y: impl Into<(f64, f64)>,
+        anchor: PixelAnchor,
+    ) -> (f64, f64) {
+        let (x, y) = image_x_y.into();
+        let offset = anchor.offset();
+        self.image_to_world((x - offset, y - offset))
+    }
+
     /// Convert world coordinates to image coordinates
+    ///
+    /// Integer image coordinates are the centres of pixels, see [`PixelAnchor::Center`].
+    ///
+    /// If this transform cannot be inverted, `(NaN, NaN)` is returned. That can only happen if
+    /// the fields have been changed after creation, see
+    /// [`try_world_to_image`](#method.try_world_to_image).
     pub fn world_to_image(&self, world_x_y: impl Into<(f64, f64)>) -> (f64, f64) {
+        self.try_world_to_image(world_x_y)
+            .unwrap_or((f64::NAN, f64::NAN))
+    }
+
+    /// Convert world coordinates to image coordinates, returning an error if this transform
+    /// cannot be inverted
+    ///
+    /// A singular transform is rejected when a world file is created or parsed, but the fields
+    /// are public, so it could have been changed since. The inverse isn't cached in the
+    /// `WorldFile` for the same reason, so this checks the determinant on every call, which is
+    /// cheap. [`inverse`](#method.inverse) is the precomputed form: when converting many points,
+    /// calculate it once, and call `image_to_world` on it.
+    pub fn try_world_to_image(
+        &self,
+        world_x_y: impl Into<(f64, f64)>,
+    ) -> Result<(f64, f64), WorldFileError> {
+        let det = self.determinant();
+        if det == 0. || !det.is_finite() {
+            return Err(WorldFileError::ZeroScale);
+        }
         let x_y = world_x_y.into();
-        let x = x_y.0;
-        let y = x_y.1;
+        // Subtracting the origin first keeps more precision with large world coordinates
+        let x = x_y.0 - self.x_coord;
+        let y = x_y.1 - self.y_coord;
 
-        let a = self.x_scale;
-        let b = self.x_skew;
-        let c = self.x_coord;
-        let d = self.y_skew;
-        let e = self.y_scale;
-        let f = self.y_coord;
+        Ok((
+            (self.y_scale * x - self.x_skew * y) / det,
+            (self.x_scale * y - self.y_skew * x) / det,
+        ))
+    }
 
-        let det = a * e - b * d; // determinate
-        assert!(det != 0.);
+    /// Convert world coordinates to image coordinates, anchored at `anchor`
+    ///
+    /// If this transform cannot be inverted, `(NaN, NaN)` is returned.
+    pub fn world_to_image_with(
+        &self,
+        world_x_y: impl Into<(f64, f64)>,
+        anchor: PixelAnchor,
+    ) -> (f64, f64) {
+        self.try_world_to_image_with(world_x_y, anchor)
+            .unwrap_or((f64::NAN, f64::NAN))
+    }
 
-        (
-            (x * e - b * y + b * f - c * e) / det,
-            (-x * d + a * y - a * f - c * d) / det,
-        )
+    /// Convert world coordinates to image coordinates, anchored at `anchor`, returning an error
+    /// if this transform cannot be inverted
+    pub fn try_world_to_image_with(
+        &self,
+        world_x_y: impl Into<(f64, f64)>,
+        anchor: PixelAnchor,
+    ) -> Result<(f64, f64), WorldFileError> {
+        let (x, y) = self.try_world_to_image(world_x_y)?;
+        let offset = anchor.offset();
+        Ok((x + offset, y + offset))
+    }
+
+    /// The inverse of this transform, which converts world coordinates to image coordinates
+    ///
+    /// Calling `image_to_world` on the inverse is equivalent to calling `world_to_image` on this
+    /// world file. When converting many points, calculate the inverse once and reuse it.
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// # let w = WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n").unwrap();
+    /// let inverse = w.inverse().unwrap();
+    /// assert_eq!(inverse.image_to_world((696672., 4565024.)), (171., 343.));
+    /// ```
+    pub fn inverse(&self) -> Result<WorldFile, WorldFileError> {
+        let det = self.determinant();
+        if det == 0. || !det.is_finite() {
+            return Err(WorldFileError::ZeroScale);
+        }
+
+        // The inverse of the 2×2 linear part…
+        let x_scale = self.y_scale / det;
+        let x_skew = -self.x_skew / det;
+        let y_skew = -self.y_skew / det;
+        let y_scale = self.x_scale / det;
+
+        // …and the translation is the original translation, undone by the inverse linear part.
+        Ok(WorldFile {
+            x_scale,
+            x_skew,
+            y_skew,
+            y_scale,
+            x_coord: -(x_scale * self.x_coord + x_skew * self.y_coord),
+            y_coord: -(y_skew * self.x_coord + y_scale * self.y_coord),
+        })
     }
+
+    /// Determinant of the linear part of the transform. Zero means it cannot be inverted.
+    fn determinant(&self) -> f64 {
+        self.x_scale * self.y_scale - self.x_skew * self.y_skew
+    }
+}
+
+/// What integer image coordinates refer to
+///
+/// The world file format defines `x_coord` & `y_coord` as the centre of the top left pixel, so
+/// [`WorldFile::image_to_world`] and [`WorldFile::world_to_image`] use `Center`. The `_with`
+/// versions of those functions let you choose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelAnchor {
+    /// `(10.0, 2.0)` is the top left corner of pixel (10, 2), and `(10.5, 2.5)` is its centre.
+    /// `(0.0, 0.0)` is the top left corner of the image.
+    Corner,
+
+    /// `(10.0, 2.0)` is the centre of pixel (10, 2), and `(9.5, 1.5)` is its top left corner.
+    /// `(0.0, 0.0)` is the centre of the top left pixel.
+    Center,
 }
 
+impl PixelAnchor {
+    /// Add this to `Center` based image coordinates to get coordinates anchored here
+    fn offset(self) -> f64 {
+        match self {
+            PixelAnchor::Corner => 0.5,
+            PixelAnchor::Center => 0.,
+        }
+    }
+}
+
+/// Write `contents` to a temporary file next to `p`, and then rename it to `p`
+///
+/// The temporary file's name is unique to this call, so concurrent writes of the same path, from
+/// any thread or process, never share one.
+fn write_atomic(p: &Path, contents: &[u8]) -> Result<(), WorldFileError> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let file_name = p
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let (tmp_path, mut f) = loop {
+        let mut tmp_name = OsString::from(".");
+        tmp_name.push(file_name);
+        tmp_name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let tmp_path = p.with_file_name(tmp_name);
+        // A left over file from an earlier process with the same pid is never overwritten
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)
+        {
+            Ok(f) => break (tmp_path, f),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    let mut write = || -> Result<(), WorldFileError> {
+        f.write_all(contents)?;
+        f.sync_all()?;
+        fs::rename(&tmp_path, p)?;
+        Ok(())
+    };
+    let result = write();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Formats this world file as the raw file content, so `to_string()` gives the file contents.
+///
+/// Numbers are written with [`WriteOptions::shortest`], so reading the string back gives exactly
+/// the same values.
 impl fmt::Display for WorldFile {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "{}", self.to_string())
+        write::format(self, &WriteOptions::default(), f)
     }
 }
 
+#[cfg(test)]
+mod test_util;
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{assert_close, test_dir};
+    use proptest::prelude::*;
 
     #[test]
     fn test_simple() {
         let test = "32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n";
-        let w = WorldFile::from_string(&test).unwrap();
+        let w = WorldFile::from_string(test).unwrap();
         assert_eq!(w.image_to_world((171., 343.)), (696672., 4565024.));
         assert_eq!(w.world_to_image((696672., 4565024.)), (171., 343.));
         let p = (100., 200.);
         assert_eq!(w.image_to_world(w.world_to_image(p)), p);
     }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n"),
+            Err(WorldFileError::MissingLine(6))
+        ));
+        match WorldFile::from_string("32.0\n0.0\nzero\n-32.0\n691200.0\n4576000.0\n") {
+            Err(WorldFileError::InvalidNumber { line, text }) => {
+                assert_eq!(line, 3);
+                assert_eq!(text, "zero");
+            }
+            r => panic!("unexpected result {:?}", r),
+        }
+        assert!(matches!(
+            WorldFile::from_string("0.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n"),
+            Err(WorldFileError::ZeroScale)
+        ));
+        assert!(matches!(
+            WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\nNaN\n4576000.0\n"),
+            Err(WorldFileError::NonFinite { line: Some(5) })
+        ));
+        assert!(matches!(
+            WorldFile::from_string("32.0\n1.0\n-32.0\n-1.0\n691200.0\n4576000.0\n"),
+            Err(WorldFileError::ZeroScale)
+        ));
+        assert!(matches!(
+            WorldFile::from_path("/does/not/exist.wld"),
+            Err(WorldFileError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_skewed() {
+        let w = WorldFile::from_string("2\n0.5\n0.25\n-2\n1000\n5000\n").unwrap();
+        assert_eq!(w.image_to_world((10., 20.)), (1025., 4965.));
+        assert_eq!(w.world_to_image((1025., 4965.)), (10., 20.));
+        let (x, y) = w.inverse().unwrap().image_to_world((1025., 4965.));
+        assert_close(x, 10., 1e-9);
+        assert_close(y, 20., 1e-9);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_round_trip(
+            scale_x in 0.001f64..1000.,
+            scale_y in 0.001f64..1000.,
+            rotation in 0f64..std::f64::consts::TAU,
+            shear in -1f64..1.,
+            flip_x: bool,
+            flip_y: bool,
+            x_coord in -1e6f64..1e6,
+            y_coord in -1e6f64..1e6,
+            image_x in -10_000f64..10_000.,
+            image_y in -10_000f64..10_000.,
+        ) {
+            // rotation × shear × scale, with optional flips
+            let scale_x = if flip_x { -scale_x } else { scale_x };
+            let scale_y = if flip_y { scale_y } else { -scale_y };
+            let (sin, cos) = rotation.sin_cos();
+            let w = WorldFile::new(
+                cos * scale_x,
+                sin * scale_x,
+                (cos * shear - sin) * scale_y,
+                (sin * shear + cos) * scale_y,
+                x_coord,
+                y_coord,
+            ).unwrap();
+
+            let image = (image_x, image_y);
+            let world = w.image_to_world(image);
+            let (x, y) = w.world_to_image(world);
+            prop_assert!((x - image_x).abs() <= 1e-6 && (y - image_y).abs() <= 1e-6);
+
+            let world = (x_coord + image_x, y_coord - image_y);
+            let size = world.0.abs().max(world.1.abs());
+            let (x, y) = w.image_to_world(w.world_to_image(world));
+            prop_assert!((x - world.0).abs() <= size * 1e-12 && (y - world.1).abs() <= size * 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_pixel_anchor() {
+        let w = WorldFile::new(2., 0., 0., -2., 101., 199.).unwrap();
+        assert_eq!(w.origin(PixelAnchor::Center), (101., 199.));
+        assert_eq!(w.origin(PixelAnchor::Corner), (100., 200.));
+
+        // Centre of pixel (10, 2)
+        assert_eq!(w.image_to_world((10., 2.)), (121., 195.));
+        assert_eq!(
+            w.image_to_world_with((10., 2.), PixelAnchor::Center),
+            (121., 195.)
+        );
+        assert_eq!(
+            w.image_to_world_with((10.5, 2.5), PixelAnchor::Corner),
+            (121., 195.)
+        );
+        assert_eq!(w.world_to_image((121., 195.)), (10., 2.));
+        assert_eq!(
+            w.world_to_image_with((121., 195.), PixelAnchor::Corner),
+            (10.5, 2.5)
+        );
+
+        // Top left corner of pixel (10, 2)
+        assert_eq!(
+            w.image_to_world_with((10., 2.), PixelAnchor::Corner),
+            (120., 196.)
+        );
+        assert_eq!(w.world_to_image((120., 196.)), (9.5, 1.5));
+        assert_eq!(
+            w.try_world_to_image_with((120., 196.), PixelAnchor::Corner)
+                .unwrap(),
+            (10., 2.)
+        );
+
+        let corner = WorldFile::from_origin(2., 0., 0., -2., (100., 200.), PixelAnchor::Corner);
+        assert_eq!(corner.unwrap(), w);
+        let center = WorldFile::from_origin(2., 0., 0., -2., (101., 199.), PixelAnchor::Center);
+        assert_eq!(center.unwrap(), w);
+
+        // Skewed
+        let w = WorldFile::from_origin(2., 1., 1., -2., (100., 200.), PixelAnchor::Corner).unwrap();
+        assert_eq!((w.x_coord, w.y_coord), (101.5, 199.5));
+        assert_eq!(w.origin(PixelAnchor::Corner), (100., 200.));
+    }
+
+    #[test]
+    fn test_singular() {
+        // Rotated 90°, so both scales are 0, but it's still a valid transform
+        let w = WorldFile::from_string("0\n-2\n-2\n0\n100\n200\n").unwrap();
+        assert_eq!(w.image_to_world((1., 0.)), (100., 198.));
+        assert_eq!(w.try_world_to_image((100., 198.)).unwrap(), (1., 0.));
+
+        let mut w = WorldFile::new(1., 0., 0., -1., 10., 20.).unwrap();
+        assert_eq!(w.try_world_to_image((11., 18.)).unwrap(), (1., 2.));
+        w.y_scale = 0.;
+        assert!(matches!(w.inverse(), Err(WorldFileError::ZeroScale)));
+        assert!(matches!(
+            w.try_world_to_image((1., 2.)),
+            Err(WorldFileError::ZeroScale)
+        ));
+        let (x, y) = w.world_to_image((1., 2.));
+        assert!(x.is_nan() && y.is_nan());
+
+        assert!(matches!(
+            WorldFile::new(1., 0., 0., f64::INFINITY, 10., 20.),
+            Err(WorldFileError::NonFinite { line: None })
+        ));
+        // Constructors with no file lines don't report one
+        let error = WorldFile::from_gdal_geotransform([0., f64::NAN, 0., 0., 0., -1.]).unwrap_err();
+        assert!(matches!(error, WorldFileError::NonFinite { line: None }));
+        assert!(!error.to_string().contains("line"));
+    }
+
+    #[test]
+    fn test_write_to_path() {
+        let dir = test_dir("write_to_path");
+        let w = WorldFile::from_string("32\n0\n0\n-32\n691200\n4576000\n").unwrap();
+
+        let path = dir.join("image.jgw");
+        w.write_to_path(&path).unwrap();
+        assert_eq!(WorldFile::from_path(&path).unwrap(), w);
+
+        let path = dir.join("image.tfw");
+        w.write_to_path_atomic(&path).unwrap();
+        assert_eq!(WorldFile::from_path(&path).unwrap(), w);
+        // Only the 2 world files, no temporary files left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        assert!(matches!(
+            w.write_to_path(dir.join("missing").join("image.tfw")),
+            Err(WorldFileError::Io(_))
+        ));
+        assert!(matches!(
+            w.write_to_path_atomic(dir.join("missing").join("image.tfw")),
+            Err(WorldFileError::Io(_))
+        ));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic_concurrently() {
+        let dir = test_dir("write_atomic_concurrently");
+        let path = dir.join("image.tfw");
+        let threads = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let w = WorldFile::new(32., 0., 0., -32., f64::from(i), 4576000.).unwrap();
+                    for _ in 0..50 {
+                        w.write_to_path_atomic(&path).unwrap();
+                        // Always a complete world file, from one of the threads
+                        let read = WorldFile::from_path(&path).unwrap();
+                        assert_eq!(read.x_scale, 32.);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_to_string() {
+        let test = "32\n0\n0\n-32\n691200\n4576000\n";
+        let w = WorldFile::from_string(test).unwrap();
+        assert_eq!(w.to_string(), test);
+        let mut out = Vec::new();
+        w.write_to_writer(&mut out).unwrap();
+        assert_eq!(out, test.as_bytes());
+    }
 }
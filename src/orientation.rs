@@ -0,0 +1,250 @@
+use crate::WorldFile;
+
+/// A rotation by a multiple of 90°, and/or a mirroring, of an image
+///
+/// These are the 8 orientations of the EXIF `Orientation` tag. Rotations are clockwise, as
+/// the image is seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    /// Unchanged
+    Normal,
+
+    /// Mirrored left to right
+    MirrorHorizontal,
+
+    /// Rotated 180°
+    Rotate180,
+
+    /// Mirrored top to bottom
+    MirrorVertical,
+
+    /// Mirrored along the top left to bottom right diagonal, i.e. mirrored left to right, and
+    /// then rotated 270°
+    Transpose,
+
+    /// Rotated 90° clockwise
+    Rotate90,
+
+    /// Mirrored along the top right to bottom left diagonal, i.e. mirrored left to right, and
+    /// then rotated 90°
+    Transverse,
+
+    /// Rotated 270° clockwise, i.e. 90° anticlockwise
+    Rotate270,
+}
+
+impl Orientation {
+    /// What to do to an image with this EXIF `Orientation` tag value (1 to 8), to display it
+    /// the right way up
+    ///
+    /// Returns `None` for other values.
+    /// ```
+    /// # use world_image_file::Orientation;
+    /// assert_eq!(Orientation::from_exif(6), Some(Orientation::Rotate90));
+    /// assert_eq!(Orientation::from_exif(9), None);
+    /// ```
+    pub fn from_exif(value: u16) -> Option<Self> {
+        match value {
+            1 => Some(Orientation::Normal),
+            2 => Some(Orientation::MirrorHorizontal),
+            3 => Some(Orientation::Rotate180),
+            4 => Some(Orientation::MirrorVertical),
+            5 => Some(Orientation::Transpose),
+            6 => Some(Orientation::Rotate90),
+            7 => Some(Orientation::Transverse),
+            8 => Some(Orientation::Rotate270),
+            _ => None,
+        }
+    }
+
+    /// The EXIF `Orientation` tag value (1 to 8) of an image which needs this done to it, see
+    /// [`from_exif`](#method.from_exif)
+    pub fn exif(&self) -> u16 {
+        match self {
+            Orientation::Normal => 1,
+            Orientation::MirrorHorizontal => 2,
+            Orientation::Rotate180 => 3,
+            Orientation::MirrorVertical => 4,
+            Orientation::Transpose => 5,
+            Orientation::Rotate90 => 6,
+            Orientation::Transverse => 7,
+            Orientation::Rotate270 => 8,
+        }
+    }
+
+    /// The `(width, height)` of an image of this size, after this is done to it
+    pub fn oriented_size(&self, width: u32, height: u32) -> (u32, u32) {
+        if self.swaps_axes() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Whether rows become columns
+    fn swaps_axes(&self) -> bool {
+        match self {
+            Orientation::Normal
+            | Orientation::MirrorHorizontal
+            | Orientation::Rotate180
+            | Orientation::MirrorVertical => false,
+            Orientation::Transpose
+            | Orientation::Rotate90
+            | Orientation::Transverse
+            | Orientation::Rotate270 => true,
+        }
+    }
+
+    /// The transform from the pixels of the oriented image to the pixels of the original, of
+    /// this size
+    fn new_to_old_pixels(&self, width: u32, height: u32) -> WorldFile {
+        // The last column & row
+        let (w, h) = (f64::from(width) - 1., f64::from(height) - 1.);
+        // (x, y) = (a x' + b y' + c, d x' + e y' + f)
+        let (a, b, c, d, e, f) = match self {
+            Orientation::Normal => (1., 0., 0., 0., 1., 0.),
+            Orientation::MirrorHorizontal => (-1., 0., w, 0., 1., 0.),
+            Orientation::Rotate180 => (-1., 0., w, 0., -1., h),
+            Orientation::MirrorVertical => (1., 0., 0., 0., -1., h),
+            Orientation::Transpose => (0., 1., 0., 1., 0., 0.),
+            Orientation::Rotate90 => (0., 1., 0., -1., 0., h),
+            Orientation::Transverse => (0., -1., w, -1., 0., h),
+            Orientation::Rotate270 => (0., -1., w, 1., 0., 0.),
+        };
+        WorldFile {
+            x_scale: a,
+            x_skew: b,
+            x_coord: c,
+            y_skew: d,
+            y_scale: e,
+            y_coord: f,
+        }
+    }
+}
+
+impl WorldFile {
+    /// The world file for this image, of this size, after it's been rotated and/or mirrored
+    ///
+    /// The oriented image covers exactly the same ground, with each pixel in the same place.
+    /// Its size is [`Orientation::oriented_size`].
+    /// ```
+    /// # use world_image_file::{WorldFile, Orientation};
+    /// let w = WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n").unwrap();
+    /// // After rotating clockwise, the image's rows go north, and its columns go east
+    /// let rotated = w.oriented(Orientation::Rotate90, 100, 50);
+    /// assert_eq!(rotated.to_string(), "0\n32\n32\n0\n691200\n4574432\n");
+    /// ```
+    pub fn oriented(&self, orientation: Orientation, width: u32, height: u32) -> WorldFile {
+        *self * orientation.new_to_old_pixels(width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PixelAnchor;
+
+    const ALL: [Orientation; 8] = [
+        Orientation::Normal,
+        Orientation::MirrorHorizontal,
+        Orientation::Rotate180,
+        Orientation::MirrorVertical,
+        Orientation::Transpose,
+        Orientation::Rotate90,
+        Orientation::Transverse,
+        Orientation::Rotate270,
+    ];
+
+    #[test]
+    fn test_exif() {
+        for &orientation in &ALL {
+            assert_eq!(
+                Orientation::from_exif(orientation.exif()),
+                Some(orientation)
+            );
+        }
+        assert_eq!(Orientation::from_exif(0), None);
+        assert_eq!(Orientation::from_exif(9), None);
+    }
+
+    #[test]
+    fn test_same_ground() {
+        let w = WorldFile::new(0.3, -0.1, 0.2, -0.4, 691200., 4576000.).unwrap();
+        let (width, height) = (7, 3);
+        let original = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (f64::from(x), f64::from(y))))
+            .map(|p| w.image_to_world(p))
+            .collect::<Vec<_>>();
+
+        for &orientation in &ALL {
+            let oriented = w.oriented(orientation, width, height);
+            let (new_width, new_height) = orientation.oriented_size(width, height);
+            let mut pixels = (0..new_height)
+                .flat_map(|y| (0..new_width).map(move |x| (f64::from(x), f64::from(y))))
+                .map(|p| oriented.image_to_world(p))
+                .collect::<Vec<_>>();
+            // Every pixel of the oriented image is on a pixel of the original
+            pixels.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let mut expected = original.clone();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            for (a, b) in pixels.iter().zip(expected.iter()) {
+                assert!(
+                    (a.0 - b.0).abs() < 1e-6 && (a.1 - b.1).abs() < 1e-6,
+                    "{:?}",
+                    orientation
+                );
+            }
+            let sizes = (oriented.pixel_width(), oriented.pixel_height());
+            if orientation.swaps_axes() {
+                assert_eq!(sizes, (w.pixel_height(), w.pixel_width()));
+            } else {
+                assert_eq!(sizes, (w.pixel_width(), w.pixel_height()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_north_up() {
+        let w = WorldFile::new(10., 0., 0., -10., 1005., 2995.).unwrap();
+        let (width, height) = (100, 30);
+        // Where the new top left pixel was, in the original
+        for &(orientation, top_left, pixel_size) in &[
+            (Orientation::Normal, (0., 0.), (10., -10.)),
+            (Orientation::MirrorHorizontal, (99., 0.), (10., 10.)),
+            (Orientation::Rotate180, (99., 29.), (10., -10.)),
+            (Orientation::MirrorVertical, (0., 29.), (10., 10.)),
+            (Orientation::Transpose, (0., 0.), (10., 10.)),
+            (Orientation::Rotate90, (0., 29.), (10., -10.)),
+            (Orientation::Transverse, (99., 29.), (10., 10.)),
+            (Orientation::Rotate270, (99., 0.), (10., -10.)),
+        ] {
+            let oriented = w.oriented(orientation, width, height);
+            assert_eq!(
+                oriented.origin(PixelAnchor::Center),
+                w.image_to_world(top_left),
+                "{:?}",
+                orientation
+            );
+            assert_eq!(oriented.pixel_size(), pixel_size, "{:?}", orientation);
+        }
+
+        // Rotating 4 times, or mirroring twice, gets back to the start
+        let mut rotated = w;
+        let (mut width, mut height) = (width, height);
+        for _ in 0..4 {
+            rotated = rotated.oriented(Orientation::Rotate90, width, height);
+            let size = Orientation::Rotate90.oriented_size(width, height);
+            width = size.0;
+            height = size.1;
+        }
+        assert_eq!(rotated, w);
+        assert_eq!(
+            w.oriented(Orientation::Transverse, width, height).oriented(
+                Orientation::Transverse,
+                height,
+                width
+            ),
+            w
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! GDAL's PAM (Persistent Auxiliary Metadata) `.aux.xml` files
+//!
+//! Only the `<GeoTransform>` and `<SRS>` elements are read & written.
+use crate::{write_atomic, WorldFile, WorldFileError};
+use std::fs;
+use std::path::Path;
+
+impl WorldFile {
+    /// Read the georeferencing from the contents of a GDAL `.aux.xml` file
+    ///
+    /// Returns the world file, and the CRS (usually as WKT) if there is one.
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// let xml = r#"<PAMDataset>
+    ///   <SRS dataAxisToSRSAxisMapping="1,2">PROJCS[&quot;WGS 84 / UTM zone 11N&quot;]</SRS>
+    ///   <GeoTransform>  4.4072000000000000e+05,  6.0000000000000000e+01,  0.0000000000000000e+00,  3.7513200000000000e+06,  0.0000000000000000e+00, -6.0000000000000000e+01</GeoTransform>
+    /// </PAMDataset>"#;
+    /// let (w, srs) = WorldFile::from_aux_xml_string(xml).unwrap();
+    /// assert_eq!((w.x_coord, w.y_coord), (440750., 3751290.));
+    /// assert_eq!(srs.as_deref(), Some(r#"PROJCS["WGS 84 / UTM zone 11N"]"#));
+    /// ```
+    pub fn from_aux_xml_string(
+        s: impl AsRef<str>,
+    ) -> Result<(Self, Option<String>), WorldFileError> {
+        let s = s.as_ref();
+        let geotransform = element_text(s, "GeoTransform")
+            .ok_or_else(|| WorldFileError::InvalidAuxXml("no <GeoTransform> element".into()))?;
+
+        let values = geotransform
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+            .filter(|values| values.len() == 6)
+            .ok_or_else(|| {
+                WorldFileError::InvalidAuxXml(format!("invalid <GeoTransform> {:?}", geotransform))
+            })?;
+        let mut geotransform = [0.; 6];
+        geotransform.copy_from_slice(&values);
+        let w = WorldFile::from_gdal_geotransform(geotransform)?;
+
+        let srs = element_text(s, "SRS")
+            .map(unescape)
+            .filter(|srs| !srs.trim().is_empty());
+        Ok((w, srs))
+    }
+
+    /// Read the georeferencing from a GDAL `.aux.xml` file
+    ///
+    /// See [`from_aux_xml_string`](#method.from_aux_xml_string).
+    pub fn from_aux_xml_path(
+        p: impl AsRef<Path>,
+    ) -> Result<(Self, Option<String>), WorldFileError> {
+        Self::from_aux_xml_string(fs::read_to_string(p)?)
+    }
+
+    /// Convert this world file, and optional CRS, to the contents of a GDAL `.aux.xml` file
+    pub fn to_aux_xml_string(&self, srs: Option<&str>) -> String {
+        let mut xml = String::from("<PAMDataset>\n");
+        if let Some(srs) = srs {
+            xml.push_str(&format!("  <SRS>{}</SRS>\n", escape(srs)));
+        }
+        let geotransform = self
+            .to_gdal_geotransform()
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        xml.push_str(&format!(
+            "  <GeoTransform>{}</GeoTransform>\n",
+            geotransform
+        ));
+        xml.push_str("</PAMDataset>\n");
+        xml
+    }
+
+    /// Write this world file, and optional CRS, to a GDAL `.aux.xml` file atomically
+    ///
+    /// GDAL looks for `map.tif.aux.xml` for `map.tif`. Any other metadata in an existing file
+    /// (e.g. statistics) is lost.
+    pub fn write_aux_xml_path(
+        &self,
+        p: impl AsRef<Path>,
+        srs: Option<&str>,
+    ) -> Result<(), WorldFileError> {
+        write_atomic(p.as_ref(), self.to_aux_xml_string(srs).as_bytes())
+    }
+}
+
+/// The raw text of the first `<name>` element
+fn element_text<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let open = format!("<{}", name);
+    let mut search_from = 0;
+    loop {
+        let start = search_from + xml[search_from..].find(&open)?;
+        let after_name = start + open.len();
+        search_from = after_name;
+        // Don't match other elements which start with the same name
+        match xml[after_name..].chars().next() {
+            Some(c) if c == '>' || c == '/' || c.is_whitespace() => {}
+            _ => continue,
+        }
+
+        let tag_end = after_name + xml[after_name..].find('>')?;
+        if xml[..tag_end].ends_with('/') {
+            return Some("");
+        }
+        let content_start = tag_end + 1;
+        let content_end = content_start + xml[content_start..].find(&format!("</{}>", name))?;
+        return Some(&xml[content_start..content_end]);
+    }
+}
+
+/// Replace XML entities & character references with the characters
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        result.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').and_then(|semi| {
+            let c = match &rest[1..semi] {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                entity if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16)
+                    .ok()
+                    .and_then(std::char::from_u32),
+                entity if entity.starts_with('#') => {
+                    entity[1..].parse().ok().and_then(std::char::from_u32)
+                }
+                _ => None,
+            };
+            c.map(|c| (c, semi))
+        });
+        match decoded {
+            Some((c, semi)) => {
+                result.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                // Not a valid reference, so keep the `&` as is
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Escape text for use as XML element content
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_dir;
+
+    #[test]
+    fn test_round_trip() {
+        let w = WorldFile::new(0.1, 0.02, -0.03, -0.1, 1.5, 50.25).unwrap();
+        let srs = r#"GEOGCS["WGS 84",DATUM["A & B <test>"]]"#;
+        let xml = w.to_aux_xml_string(Some(srs));
+        assert!(xml.contains("A &amp; B &lt;test&gt;"));
+        let (w2, srs2) = WorldFile::from_aux_xml_string(&xml).unwrap();
+        assert_eq!(w2.to_gdal_geotransform(), w.to_gdal_geotransform());
+        assert_eq!(srs2.as_deref(), Some(srs));
+
+        let (_, srs) = WorldFile::from_aux_xml_string(w.to_aux_xml_string(None)).unwrap();
+        assert_eq!(srs, None);
+    }
+
+    #[test]
+    fn test_gdal_file() {
+        // In the style GDAL 3 writes, with band statistics, plus a similarly named element
+        let xml = r#"<PAMDataset>
+  <SRS dataAxisToSRSAxisMapping="2,1">GEOGCS["WGS 84",AUTHORITY["EPSG","4326"]]</SRS>
+  <GeoTransformation>ignore me</GeoTransformation>
+  <GeoTransform> -1.8000000000000000e+02,  5.0000000000000000e-01,  0.0000000000000000e+00,  9.0000000000000000e+01,  0.0000000000000000e+00, -5.0000000000000000e-01</GeoTransform>
+  <PAMRasterBand band="1">
+    <Metadata>
+      <MDI key="STATISTICS_MAXIMUM">255</MDI>
+    </Metadata>
+  </PAMRasterBand>
+</PAMDataset>
+"#;
+        let (w, srs) = WorldFile::from_aux_xml_string(xml).unwrap();
+        assert_eq!(
+            w,
+            WorldFile::new(0.5, 0., 0., -0.5, -179.75, 89.75).unwrap()
+        );
+        assert_eq!(
+            srs.as_deref(),
+            Some(r#"GEOGCS["WGS 84",AUTHORITY["EPSG","4326"]]"#)
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        for xml in &[
+            "<PAMDataset><SRS>x</SRS></PAMDataset>",
+            "<PAMDataset><GeoTransform>1, 2, 3</GeoTransform></PAMDataset>",
+            "<PAMDataset><GeoTransform>1, 2, 3, 4, 5, x</GeoTransform></PAMDataset>",
+            "<PAMDataset><GeoTransform>1, 2, 3, 4, 5, 6",
+        ] {
+            assert!(
+                matches!(
+                    WorldFile::from_aux_xml_string(xml),
+                    Err(WorldFileError::InvalidAuxXml(_))
+                ),
+                "{}",
+                xml
+            );
+        }
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape("a &lt;&amp;&gt; b"), "a <&> b");
+        assert_eq!(unescape("&#65;&#x42;&quot;&apos;"), "AB\"'");
+        assert_eq!(unescape("A & B &unknown; &"), "A & B &unknown; &");
+    }
+
+    #[test]
+    fn test_path() {
+        let dir = test_dir("aux_xml");
+        let w = WorldFile::new(32., 0., 0., -32., 691200., 4576000.).unwrap();
+        let path = dir.join("map.tif.aux.xml");
+        w.write_aux_xml_path(&path, Some("LOCAL_CS[\"x\"]"))
+            .unwrap();
+        assert_eq!(
+            WorldFile::from_aux_xml_path(&path).unwrap(),
+            (w, Some("LOCAL_CS[\"x\"]".to_string()))
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
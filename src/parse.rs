@@ -0,0 +1,411 @@
+use crate::{WorldFile, WorldFileError};
+use std::fmt;
+
+/// How strictly to parse a world file
+///
+/// Used with [`WorldFile::from_string_with`](crate::WorldFile::from_string_with) and friends.
+/// The default is [`lenient`](#method.lenient), which is what
+/// [`WorldFile::from_string`](crate::WorldFile::from_string) uses.
+/// ```
+/// use world_image_file::{WorldFile, ParseOptions, Diagnostic};
+/// let contents = "\u{feff}32,0\r\n0\r\n0\r\n-32,0\r\n691200,0\r\n4576000,0\r\n";
+/// let options = ParseOptions::lenient().allow_comma_decimals(true);
+/// let (w, diagnostics) = WorldFile::from_string_with(contents, &options).unwrap();
+/// assert_eq!(w.x_scale, 32.);
+/// assert_eq!(diagnostics[0], Diagnostic::ByteOrderMark);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    lenient: bool,
+    comma_decimals: bool,
+    extra_lines: bool,
+    comments: bool,
+    max_magnitude: Option<f64>,
+}
+
+impl ParseOptions {
+    /// Largest absolute value that [`strict`](#method.strict) accepts
+    ///
+    /// This is much larger than any real coordinate, or pixel size, on Earth in metres or degrees,
+    /// so anything larger is almost certainly a mistake.
+    pub const DEFAULT_MAX_MAGNITUDE: f64 = 1e10;
+
+    /// Only accept six lines, each with just a number, and nothing else apart from blank lines
+    /// at the end.
+    ///
+    /// Values larger than [`DEFAULT_MAX_MAGNITUDE`](#associatedconstant.DEFAULT_MAX_MAGNITUDE)
+    /// are rejected as absurd.
+    pub fn strict() -> Self {
+        ParseOptions {
+            lenient: false,
+            comma_decimals: false,
+            extra_lines: false,
+            comments: false,
+            max_magnitude: Some(Self::DEFAULT_MAX_MAGNITUDE),
+        }
+    }
+
+    /// Accept the things real world files often have: a UTF-8 byte order mark, whitespace
+    /// around numbers, Fortran style exponents (`1.0D+02`), and extra lines after the six
+    /// values.
+    ///
+    /// Like [`strict`](#method.strict), a blank line before the six values is an error, since
+    /// it usually means a value is missing.
+    pub fn lenient() -> Self {
+        ParseOptions {
+            lenient: true,
+            comma_decimals: false,
+            extra_lines: true,
+            comments: false,
+            max_magnitude: None,
+        }
+    }
+
+    /// Accept a comma as the decimal separator (`32,5`), as written by some European software
+    pub fn allow_comma_decimals(mut self, allow: bool) -> Self {
+        self.comma_decimals = allow;
+        self
+    }
+
+    /// Ignore any non-blank lines after the six values, rather than returning
+    /// [`WorldFileError::TrailingGarbage`]
+    pub fn allow_extra_lines(mut self, allow: bool) -> Self {
+        self.extra_lines = allow;
+        self
+    }
+
+    /// Reject any value whose absolute value is larger than this, with
+    /// [`WorldFileError::OutOfRange`]. `None` accepts any finite value.
+    pub fn max_magnitude(mut self, max_magnitude: Option<f64>) -> Self {
+        self.max_magnitude = max_magnitude;
+        self
+    }
+
+    /// Ignore everything after a `#` on a line
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.comments = allow;
+        self
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::lenient()
+    }
+}
+
+/// Something unusual in a world file, which was accepted when parsing
+///
+/// Line numbers are 1-based.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Diagnostic {
+    /// The file starts with a UTF-8 byte order mark
+    ByteOrderMark,
+
+    /// This line has whitespace around the number
+    Whitespace { line: usize },
+
+    /// This line, after the six values, is blank
+    BlankLine { line: usize },
+
+    /// This line uses a Fortran style `D` exponent
+    FortranExponent { line: usize },
+
+    /// This line uses a comma as decimal separator
+    CommaDecimal { line: usize },
+
+    /// This line has a comment
+    Comment { line: usize },
+
+    /// This line is after the six values, and was ignored
+    ExtraLine { line: usize },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Diagnostic::ByteOrderMark => write!(f, "file starts with a byte order mark"),
+            Diagnostic::Whitespace { line } => write!(f, "line {}: whitespace around number", line),
+            Diagnostic::BlankLine { line } => write!(f, "line {}: blank line", line),
+            Diagnostic::FortranExponent { line } => {
+                write!(f, "line {}: Fortran style exponent", line)
+            }
+            Diagnostic::CommaDecimal { line } => {
+                write!(f, "line {}: comma as decimal separator", line)
+            }
+            Diagnostic::Comment { line } => write!(f, "line {}: comment", line),
+            Diagnostic::ExtraLine { line } => write!(f, "line {}: extra line ignored", line),
+        }
+    }
+}
+
+/// Parse the contents of a world file
+pub(crate) fn parse(
+    s: &str,
+    options: &ParseOptions,
+) -> Result<(WorldFile, Vec<Diagnostic>), WorldFileError> {
+    let mut diagnostics = Vec::new();
+    let mut s = s;
+    if options.lenient && s.starts_with('\u{feff}') {
+        s = &s['\u{feff}'.len_utf8()..];
+        diagnostics.push(Diagnostic::ByteOrderMark);
+    }
+
+    let mut values = Vec::with_capacity(6);
+    let mut num_lines = 0;
+    for (idx, text) in s.lines().enumerate() {
+        let line = idx + 1;
+        num_lines = line;
+
+        let mut text = text;
+        if options.comments {
+            if let Some(comment_start) = text.find('#') {
+                text = &text[..comment_start];
+                diagnostics.push(Diagnostic::Comment { line });
+                if text.trim().is_empty() {
+                    continue;
+                }
+            }
+        }
+
+        // Only after the six values, so a blank value line can't silently shift the rest up
+        if text.trim().is_empty() && values.len() == 6 {
+            diagnostics.push(Diagnostic::BlankLine { line });
+            continue;
+        }
+
+        if values.len() == 6 {
+            if !options.extra_lines {
+                return Err(WorldFileError::TrailingGarbage { line });
+            }
+            diagnostics.push(Diagnostic::ExtraLine { line });
+            continue;
+        }
+
+        if options.lenient && text.trim() != text {
+            text = text.trim();
+            diagnostics.push(Diagnostic::Whitespace { line });
+        }
+
+        let value = parse_number(text, line, options, &mut diagnostics)?;
+        if !value.is_finite() {
+            return Err(WorldFileError::NonFinite { line: Some(line) });
+        }
+        if let Some(max_magnitude) = options.max_magnitude {
+            if value.abs() > max_magnitude {
+                return Err(WorldFileError::OutOfRange { line, value });
+            }
+        }
+        values.push(value);
+    }
+
+    if values.len() < 6 {
+        return Err(WorldFileError::MissingLine(num_lines + 1));
+    }
+
+    let w = WorldFile::new(
+        values[0], values[1], values[2], values[3], values[4], values[5],
+    )?;
+    Ok((w, diagnostics))
+}
+
+/// Parse one number, converting Fortran exponents & comma decimals if allowed
+fn parse_number(
+    text: &str,
+    line: usize,
+    options: &ParseOptions,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<f64, WorldFileError> {
+    if let Ok(value) = text.parse() {
+        return Ok(value);
+    }
+
+    let invalid = || WorldFileError::InvalidNumber {
+        line,
+        text: text.to_string(),
+    };
+    let mut normalised = text.to_string();
+    let mut tolerated = Vec::new();
+    if options.lenient && normalised.contains(['d', 'D']) {
+        normalised = normalised.replace(['d', 'D'], "e");
+        tolerated.push(Diagnostic::FortranExponent { line });
+    }
+    if options.comma_decimals && !normalised.contains('.') && normalised.matches(',').count() == 1 {
+        normalised = normalised.replace(',', ".");
+        tolerated.push(Diagnostic::CommaDecimal { line });
+    }
+    if tolerated.is_empty() {
+        return Err(invalid());
+    }
+
+    let value = normalised.parse().map_err(|_| invalid())?;
+    diagnostics.extend(tolerated);
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPECTED: WorldFile = WorldFile {
+        x_scale: 32.,
+        y_skew: 0.,
+        x_skew: 0.,
+        y_scale: -32.,
+        x_coord: 691200.,
+        y_coord: 4576000.,
+    };
+
+    #[test]
+    fn test_lenient() {
+        let contents =
+            "\u{feff} 32.0\r\n0\r\n0.0D+00\r\n-3.2d1\t\r\n691200\r\n4576000\r\nextra\r\n\r\n";
+        let (w, diagnostics) = parse(contents, &ParseOptions::lenient()).unwrap();
+        assert_eq!(w, EXPECTED);
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::ByteOrderMark,
+                Diagnostic::Whitespace { line: 1 },
+                Diagnostic::FortranExponent { line: 3 },
+                Diagnostic::Whitespace { line: 4 },
+                Diagnostic::FortranExponent { line: 4 },
+                Diagnostic::ExtraLine { line: 7 },
+                Diagnostic::BlankLine { line: 8 },
+            ]
+        );
+
+        // Plain files have nothing to report
+        let (w, diagnostics) =
+            parse("32\n0\n0\n-32\n691200\n4576000\n", &ParseOptions::lenient()).unwrap();
+        assert_eq!(w, EXPECTED);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_blank_value_line() {
+        // A blank x_skew line, with an extra line at the end, must not shift the values up
+        let contents = "32\n0\n\n-32\n691200\n4576000\n1\n";
+        for options in &[ParseOptions::lenient(), ParseOptions::strict()] {
+            assert!(matches!(
+                parse(contents, options),
+                Err(WorldFileError::InvalidNumber { line: 3, .. })
+            ));
+        }
+        assert!(WorldFile::from_string(contents).is_err());
+    }
+
+    #[test]
+    fn test_comma_decimals() {
+        let contents = "32,0\n0\n0\n-32,0\n691200,0\n4576000,0\n";
+        assert!(matches!(
+            parse(contents, &ParseOptions::lenient()),
+            Err(WorldFileError::InvalidNumber { line: 1, .. })
+        ));
+        let options = ParseOptions::lenient().allow_comma_decimals(true);
+        let (w, diagnostics) = parse(contents, &options).unwrap();
+        assert_eq!(w, EXPECTED);
+        assert_eq!(diagnostics.len(), 4);
+        assert_eq!(diagnostics[3], Diagnostic::CommaDecimal { line: 6 });
+
+        // Thousands separators are not decimal separators
+        assert!(parse("32\n0\n0\n-32\n691,200.0\n4576000\n", &options).is_err());
+    }
+
+    #[test]
+    fn test_comments() {
+        let contents = "# made by hand\n32 # x scale\n0\n0\n-32\n691200\n4576000\n";
+        assert!(parse(contents, &ParseOptions::lenient()).is_err());
+        let options = ParseOptions::lenient().allow_comments(true);
+        let (w, diagnostics) = parse(contents, &options).unwrap();
+        assert_eq!(w, EXPECTED);
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::Comment { line: 1 },
+                Diagnostic::Comment { line: 2 },
+                Diagnostic::Whitespace { line: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        let strict = ParseOptions::strict();
+        let (w, diagnostics) =
+            parse("32\r\n0\r\n0\r\n-32\r\n691200\r\n4576000\r\n", &strict).unwrap();
+        assert_eq!(w, EXPECTED);
+        assert!(diagnostics.is_empty());
+
+        assert!(matches!(
+            parse("\u{feff}32\n0\n0\n-32\n691200\n4576000\n", &strict),
+            Err(WorldFileError::InvalidNumber { line: 1, .. })
+        ));
+        assert!(matches!(
+            parse("32\n0\n0 \n-32\n691200\n4576000\n", &strict),
+            Err(WorldFileError::InvalidNumber { line: 3, .. })
+        ));
+        assert!(matches!(
+            parse("32\n0\n\n0\n-32\n691200\n4576000\n", &strict),
+            Err(WorldFileError::InvalidNumber { line: 3, .. })
+        ));
+        assert!(matches!(
+            parse("32\n0\n0\n-32\n691200\n4.576D6\n", &strict),
+            Err(WorldFileError::InvalidNumber { line: 6, .. })
+        ));
+        assert!(matches!(
+            parse("32\n0\n0\n-32\n691200\n4576000\nextra\n", &strict),
+            Err(WorldFileError::TrailingGarbage { line: 7 })
+        ));
+        assert!(parse(
+            "32\n0\n0\n-32\n691200\n4576000\nextra\n",
+            &strict.allow_extra_lines(true)
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_strict_values() {
+        let strict = ParseOptions::strict();
+        for (contents, line) in &[
+            ("32\n0\n0\n-32\nNaN\n4576000\n", 5),
+            ("32\n0\n0\n-32\n691200\ninf\n", 6),
+            ("32\n-infinity\n0\n-32\n691200\n4576000\n", 2),
+        ] {
+            match parse(contents, &strict) {
+                Err(WorldFileError::NonFinite { line: l }) => assert_eq!(l, Some(*line)),
+                r => panic!("unexpected result {:?}", r),
+            }
+        }
+
+        let absurd = "32\n0\n0\n-32\n691200\n4.576e20\n";
+        match parse(absurd, &strict) {
+            Err(WorldFileError::OutOfRange { line, value }) => {
+                assert_eq!(line, 6);
+                assert_eq!(value, 4.576e20);
+            }
+            r => panic!("unexpected result {:?}", r),
+        }
+        assert!(parse(absurd, &ParseOptions::lenient()).is_ok());
+        assert!(parse(absurd, &strict.clone().max_magnitude(None)).is_ok());
+        assert!(parse(
+            "32\n0\n0\n-32\n691200\n4576000\n",
+            &strict.max_magnitude(Some(1e6))
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_missing_line() {
+        assert!(matches!(
+            parse("32\n0\n0\n-32\n691200\n", &ParseOptions::lenient()),
+            Err(WorldFileError::MissingLine(6))
+        ));
+        // A blank line is the missing value, not skipped
+        assert!(matches!(
+            parse("32\n\n0\n0\n-32\n691200\n", &ParseOptions::lenient()),
+            Err(WorldFileError::InvalidNumber { line: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{WorldFile, WorldFileError};
+
+impl WorldFile {
+    /// The world file for this image, resized from `old_width` × `old_height` pixels to
+    /// `new_width` × `new_height`, e.g. for a thumbnail or overview
+    ///
+    /// The resized image covers exactly the same area. Since `x_coord`/`y_coord` are the centre
+    /// of the top left pixel, which moves when the pixels change size, they change too. This
+    /// works for rotated & skewed images.
+    ///
+    /// Returns [`WorldFileError::InvalidImageSize`] if any size is zero.
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// let w = WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n").unwrap();
+    /// let thumbnail = w.resized(1000, 800, 250, 200).unwrap();
+    /// assert_eq!(thumbnail.to_string(), "128\n0\n0\n-128\n691248\n4575952\n");
+    /// ```
+    pub fn resized(
+        &self,
+        old_width: u32,
+        old_height: u32,
+        new_width: u32,
+        new_height: u32,
+    ) -> Result<WorldFile, WorldFileError> {
+        if old_width == 0 || old_height == 0 || new_width == 0 || new_height == 0 {
+            return Err(WorldFileError::InvalidImageSize);
+        }
+        let sx = f64::from(old_width) / f64::from(new_width);
+        let sy = f64::from(old_height) / f64::from(new_height);
+        Ok(*self * new_to_old_pixels(sx, sy))
+    }
+
+    /// The world file for this image, resized by `factor`, e.g. `0.5` for an image half the
+    /// width & height
+    ///
+    /// The same as [`resized`](#method.resized), when the new size is exactly the old size
+    /// times `factor`. Returns [`WorldFileError::NonFinite`] if `factor` is `NaN` or infinite,
+    /// and [`WorldFileError::InvalidImageSize`] if it's zero or negative.
+    pub fn scaled_by(&self, factor: f64) -> Result<WorldFile, WorldFileError> {
+        if !factor.is_finite() {
+            return Err(WorldFileError::NonFinite { line: None });
+        }
+        if factor <= 0. {
+            return Err(WorldFileError::InvalidImageSize);
+        }
+        Ok(*self * new_to_old_pixels(1. / factor, 1. / factor))
+    }
+}
+
+/// The transform from a resized image's pixels to the original image's pixels, when each new
+/// pixel is `sx` × `sy` old pixels
+///
+/// Pixel edges scale by `sx`, so `x_old + 0.5 = (x_new + 0.5) * sx` for pixel centres.
+fn new_to_old_pixels(sx: f64, sy: f64) -> WorldFile {
+    WorldFile {
+        x_scale: sx,
+        y_skew: 0.,
+        x_skew: 0.,
+        y_scale: sy,
+        x_coord: (sx - 1.) / 2.,
+        y_coord: (sy - 1.) / 2.,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    /// The GeoTransform GDAL gives an overview of a dataset with this GeoTransform & size
+    ///
+    /// GDAL (`GDALDataset::GetGeoTransform` on an overview, e.g. via
+    /// `gdal.Open(…, open_options=['OVERVIEW_LEVEL=0'])`) keeps the origin, and scales the pixel
+    /// size & skew by the ratio of the sizes.
+    fn gdal_overview_geotransform(
+        geotransform: [f64; 6],
+        (width, height): (u32, u32),
+        (overview_width, overview_height): (u32, u32),
+    ) -> [f64; 6] {
+        let sx = f64::from(width) / f64::from(overview_width);
+        let sy = f64::from(height) / f64::from(overview_height);
+        let [x_origin, x_scale, x_skew, y_origin, y_skew, y_scale] = geotransform;
+        [
+            x_origin,
+            x_scale * sx,
+            x_skew * sy,
+            y_origin,
+            y_skew * sx,
+            y_scale * sy,
+        ]
+    }
+
+    #[test]
+    fn test_gdal_overviews() {
+        for &geotransform in &[
+            [440720., 60., 0., 3751320., 0., -60.],
+            [-180., 0.5, 0., 90., 0., -0.5],
+            // Rotated & skewed
+            [1000., 3., 4., 2000., 4., -3.],
+            [691184., 30., -2.5, 4576016., 1.25, -28.],
+        ] {
+            let w = WorldFile::from_gdal_geotransform(geotransform).unwrap();
+            // gdaladdo's overview sizes are rounded up, e.g. 1001 / 2 is 501
+            for &(size, overview) in &[
+                ((1000, 800), (500, 400)),
+                ((1001, 799), (501, 400)),
+                ((1001, 799), (126, 100)),
+                ((7, 3), (1, 1)),
+            ] {
+                let resized = w.resized(size.0, size.1, overview.0, overview.1).unwrap();
+                let expected = gdal_overview_geotransform(geotransform, size, overview);
+                for (&a, &b) in resized.to_gdal_geotransform().iter().zip(&expected) {
+                    assert_close(a, b, 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_corners() {
+        // The resized image covers the same area, even when rotated
+        let w = WorldFile::from_gdal_geotransform([1000., 3., 4., 2000., 4., -3.]).unwrap();
+        let resized = w.resized(640, 480, 100, 75).unwrap();
+        for (a, b) in resized
+            .corners(100, 75)
+            .iter()
+            .zip(w.corners(640, 480).iter())
+        {
+            assert!((a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9);
+        }
+
+        // Enlarging works too
+        let larger = w.resized(640, 480, 1280, 960).unwrap();
+        assert_eq!(larger.x_scale, 1.5);
+        assert_eq!(larger.corners(1280, 960), w.corners(640, 480));
+    }
+
+    #[test]
+    fn test_scaled_by() {
+        let w = WorldFile::new(32., 0., 0., -32., 691200., 4576000.).unwrap();
+        assert_eq!(
+            w.scaled_by(0.25).unwrap(),
+            w.resized(1000, 800, 250, 200).unwrap()
+        );
+        assert_eq!(
+            w.scaled_by(2.).unwrap(),
+            w.resized(100, 80, 200, 160).unwrap()
+        );
+        assert_eq!(w.scaled_by(1.).unwrap(), w);
+
+        for &factor in &[0., -1.] {
+            assert!(matches!(
+                w.scaled_by(factor),
+                Err(WorldFileError::InvalidImageSize)
+            ));
+        }
+        for &factor in &[f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                w.scaled_by(factor),
+                Err(WorldFileError::NonFinite { line: None })
+            ));
+        }
+        assert!(matches!(
+            w.resized(0, 10, 10, 10),
+            Err(WorldFileError::InvalidImageSize)
+        ));
+    }
+}
@@ -0,0 +1,312 @@
+use crate::{write_atomic, WorldFile, WorldFileError};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Which file extension to use when writing a world file for an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SidecarStyle {
+    /// First and last letters of the image extension, plus `w`, e.g. `map.jgw` for `map.jpg`.
+    /// This is the most widely supported.
+    ThreeLetter,
+
+    /// The full image extension plus `w`, e.g. `map.jpgw` for `map.jpg`
+    FourLetter,
+
+    /// Always `.wld`, e.g. `map.wld`
+    Wld,
+}
+
+impl WorldFile {
+    /// Find, and open, the world file for this image
+    ///
+    /// The conventional world file names ("sidecars") are tried in order, ignoring case: the
+    /// first and last letters of the image extension plus `w` (`image.jgw` for `image.jpg`), the
+    /// full image extension plus `w` (`image.jpgw`), and `image.wld`. Finally the GeoTransform
+    /// in a GDAL `image.jpg.aux.xml` file is used. Names with a lower or upper case extension
+    /// are preferred, and the directory is only read if there are none.
+    ///
+    /// Returns the world file and the path it was read from.
+    /// ```no_run
+    /// # use world_image_file::WorldFile;
+    /// let (w, path) = WorldFile::for_image("scans/map.tif").unwrap();
+    /// println!("Read {}", path.display());
+    /// ```
+    pub fn for_image(image_path: impl AsRef<Path>) -> Result<(Self, PathBuf), WorldFileError> {
+        let (w, _srs, path) = read_for_image(&mut Sidecars::new(image_path.as_ref()))?;
+        Ok((w, path))
+    }
+
+    /// Write this as the world file for this image, with the conventional file name
+    ///
+    /// If `prj` is given, it's written as the CRS (e.g. WKT) to a `.prj` file, e.g. `map.prj`
+    /// for `map.jpg`. Both files are written atomically. If the image has no extension, `.wld`
+    /// is used for every style.
+    ///
+    /// Returns the path of the world file.
+    /// ```no_run
+    /// # use world_image_file::{WorldFile, SidecarStyle};
+    /// # let w = WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n").unwrap();
+    /// let path = w.write_for_image("map.jpg", SidecarStyle::ThreeLetter, None).unwrap();
+    /// assert_eq!(path.to_str(), Some("map.jgw"));
+    /// ```
+    pub fn write_for_image(
+        &self,
+        image_path: impl AsRef<Path>,
+        style: SidecarStyle,
+        prj: Option<&str>,
+    ) -> Result<PathBuf, WorldFileError> {
+        let image_path = image_path.as_ref();
+        let path = world_file_path(image_path, style);
+        self.write_to_path_atomic(&path)?;
+        if let Some(prj) = prj {
+            write_atomic(&prj_path(image_path), prj.as_bytes())?;
+        }
+        Ok(path)
+    }
+}
+
+/// Find, and read, the world file for an image, as for [`WorldFile::for_image`]
+///
+/// Also returns the CRS if it was read from a GDAL `.aux.xml` file.
+pub(crate) fn read_for_image(
+    sidecars: &mut Sidecars,
+) -> Result<(WorldFile, Option<String>, PathBuf), WorldFileError> {
+    let image_path = sidecars.image_path;
+    if let Some(path) = sidecars.find(&world_file_extensions(image_path))? {
+        let w = WorldFile::from_path(&path)?;
+        return Ok((w, None, path));
+    }
+    if let Some(path) = sidecars.find(&[aux_xml_extension(image_path)])? {
+        let (w, srs) = WorldFile::from_aux_xml_path(&path)?;
+        return Ok((w, srs, path));
+    }
+    Err(WorldFileError::SidecarNotFound(image_path.to_path_buf()))
+}
+
+/// The path of the `.prj` file to write for this image or world file, e.g. `map.prj`
+pub(crate) fn prj_path(path: &Path) -> PathBuf {
+    path.with_extension("prj")
+}
+
+/// The path of the world file for this image, in this style
+pub(crate) fn world_file_path(image_path: &Path, style: SidecarStyle) -> PathBuf {
+    let mut extensions = world_file_extensions(image_path);
+    let ext = match style {
+        SidecarStyle::ThreeLetter if extensions.len() > 1 => extensions.remove(0),
+        SidecarStyle::FourLetter if extensions.len() > 1 => extensions.remove(1),
+        _ => "wld".to_string(),
+    };
+    image_path.with_extension(ext)
+}
+
+/// The extensions a world file for this image could have, most preferred first
+pub(crate) fn world_file_extensions(image_path: &Path) -> Vec<String> {
+    let ext = image_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let ext = match ext.as_str() {
+        "jpeg" => "jpg",
+        "tiff" => "tif",
+        ext => ext,
+    };
+
+    let mut extensions = Vec::new();
+    let mut chars = ext.chars();
+    if let (Some(first), Some(last)) = (chars.next(), chars.last()) {
+        extensions.push(format!("{}{}w", first, last));
+        extensions.push(format!("{}w", ext));
+    }
+    extensions.push("wld".to_string());
+    extensions
+}
+
+/// The "extension" of a GDAL `.aux.xml` file for this image, i.e. `jpg.aux.xml` for `map.jpg`
+pub(crate) fn aux_xml_extension(image_path: &Path) -> String {
+    match image_path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}.aux.xml", ext),
+        None => "aux.xml".to_string(),
+    }
+}
+
+/// The files next to an image, e.g. `map.jgw` for `map.jpg`, found ignoring case
+///
+/// Names in lower or upper case (`map.jgw` or `map.JGW`) are looked for directly. The directory
+/// is only read, at most once, if there are none, to find names in mixed case.
+pub(crate) struct Sidecars<'a> {
+    image_path: &'a Path,
+    /// The lowercase & actual names of the files in the directory, once it's been read
+    files: Option<Vec<(String, String)>>,
+}
+
+impl<'a> Sidecars<'a> {
+    pub(crate) fn new(image_path: &'a Path) -> Self {
+        Sidecars {
+            image_path,
+            files: None,
+        }
+    }
+
+    /// The first file with one of these extensions, preferring lower or upper case names
+    ///
+    /// `Ok(None)` means no such file exists.
+    pub(crate) fn find(
+        &mut self,
+        extensions: &[String],
+    ) -> Result<Option<PathBuf>, WorldFileError> {
+        let stem = match self.image_path.file_stem().and_then(|s| s.to_str()) {
+            Some(stem) => stem,
+            None => return Ok(None),
+        };
+        let dir = match self.image_path.parent() {
+            Some(dir) if dir != Path::new("") => dir,
+            _ => Path::new("."),
+        };
+
+        for ext in extensions {
+            for ext in &[ext.to_lowercase(), ext.to_uppercase()] {
+                let path = dir.join(format!("{}.{}", stem, ext));
+                if path.is_file() {
+                    return Ok(Some(path));
+                }
+            }
+        }
+
+        if self.files.is_none() {
+            let mut files = Vec::new();
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                if let Ok(name) = entry.file_name().into_string() {
+                    files.push((name.to_lowercase(), name));
+                }
+            }
+            // Directory order is arbitrary, so sort to always pick the same file
+            files.sort();
+            self.files = Some(files);
+        }
+        let files = self.files.as_ref().unwrap();
+
+        for ext in extensions {
+            let wanted = format!("{}.{}", stem, ext).to_lowercase();
+            if let Some((_, name)) = files.iter().find(|(lower, _)| *lower == wanted) {
+                return Ok(Some(dir.join(name)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The `.prj` file, see [`find`](#method.find)
+    pub(crate) fn find_prj(&mut self) -> Result<Option<PathBuf>, WorldFileError> {
+        self.find(&["prj".to_string()])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_dir;
+
+    #[test]
+    fn test_world_file_extensions() {
+        let exts = |p: &str| world_file_extensions(Path::new(p));
+        assert_eq!(exts("a.jpg"), vec!["jgw", "jpgw", "wld"]);
+        assert_eq!(exts("a.JPEG"), vec!["jgw", "jpgw", "wld"]);
+        assert_eq!(exts("a/b.tif"), vec!["tfw", "tifw", "wld"]);
+        assert_eq!(exts("a.tiff"), vec!["tfw", "tifw", "wld"]);
+        assert_eq!(exts("a.png"), vec!["pgw", "pngw", "wld"]);
+        assert_eq!(exts("a.gif"), vec!["gfw", "gifw", "wld"]);
+        assert_eq!(exts("a.bmp"), vec!["bpw", "bmpw", "wld"]);
+        assert_eq!(exts("a"), vec!["wld"]);
+    }
+
+    #[test]
+    fn test_world_file_path() {
+        let path = |p: &str, style| world_file_path(Path::new(p), style);
+        assert_eq!(path("a.jpg", SidecarStyle::ThreeLetter), Path::new("a.jgw"));
+        assert_eq!(path("a.jpg", SidecarStyle::FourLetter), Path::new("a.jpgw"));
+        assert_eq!(path("a.jpg", SidecarStyle::Wld), Path::new("a.wld"));
+        assert_eq!(
+            path("x/a.TIFF", SidecarStyle::ThreeLetter),
+            Path::new("x/a.tfw")
+        );
+        assert_eq!(path("a.PNG", SidecarStyle::FourLetter), Path::new("a.pngw"));
+        assert_eq!(path("a", SidecarStyle::ThreeLetter), Path::new("a.wld"));
+    }
+
+    #[test]
+    fn test_write_for_image() {
+        let dir = test_dir("write_for_image");
+        let w = WorldFile::new(32., 0., 0., -32., 691200., 4576000.).unwrap();
+        let image = dir.join("map.gif");
+
+        let path = w
+            .write_for_image(&image, SidecarStyle::ThreeLetter, None)
+            .unwrap();
+        assert_eq!(path, dir.join("map.gfw"));
+        assert!(!dir.join("map.prj").exists());
+        assert_eq!(WorldFile::for_image(&image).unwrap(), (w, path));
+
+        let wkt = r#"GEOGCS["WGS 84",AUTHORITY["EPSG","4326"]]"#;
+        let path = w
+            .write_for_image(&image, SidecarStyle::Wld, Some(wkt))
+            .unwrap();
+        assert_eq!(path, dir.join("map.wld"));
+        assert_eq!(fs::read_to_string(dir.join("map.prj")).unwrap(), wkt);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_for_image() {
+        let dir = test_dir("for_image");
+        let w = WorldFile::new(32., 0., 0., -32., 691200., 4576000.).unwrap();
+
+        let image = dir.join("map.JPG");
+        assert!(matches!(
+            WorldFile::for_image(&image),
+            Err(WorldFileError::SidecarNotFound(_))
+        ));
+
+        w.write_to_path(dir.join("map.wld")).unwrap();
+        assert_eq!(
+            WorldFile::for_image(&image).unwrap(),
+            (w, dir.join("map.wld"))
+        );
+
+        w.write_to_path(dir.join("map.JPGW")).unwrap();
+        assert_eq!(
+            WorldFile::for_image(&image).unwrap().1,
+            dir.join("map.JPGW")
+        );
+
+        w.write_to_path(dir.join("map.jgw")).unwrap();
+        assert_eq!(WorldFile::for_image(&image).unwrap().1, dir.join("map.jgw"));
+
+        // A world file for a different image type isn't used, but a GDAL .aux.xml file is
+        let png = dir.join("map.png");
+        w.write_aux_xml_path(dir.join("map.png.aux.xml"), None)
+            .unwrap();
+        fs::remove_file(dir.join("map.wld")).unwrap();
+        assert_eq!(
+            WorldFile::for_image(&png).unwrap(),
+            (w, dir.join("map.png.aux.xml"))
+        );
+        w.write_to_path(dir.join("map.wld")).unwrap();
+        assert_eq!(WorldFile::for_image(&png).unwrap().1, dir.join("map.wld"));
+
+        assert_eq!(
+            WorldFile::for_image(dir.join("map.tif")).unwrap().1,
+            dir.join("map.wld")
+        );
+
+        // Mixed case names are found by reading the directory, if there's no other file
+        let scan = dir.join("scan.tif");
+        w.write_to_path(dir.join("scan.Tfw")).unwrap();
+        assert_eq!(WorldFile::for_image(&scan).unwrap().1, dir.join("scan.Tfw"));
+        w.write_to_path(dir.join("scan.WLD")).unwrap();
+        assert_eq!(WorldFile::for_image(&scan).unwrap().1, dir.join("scan.WLD"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,22 @@
+//! Helpers shared by the tests of every module
+use std::fs;
+use std::path::PathBuf;
+
+/// A new empty directory for a test to write files to
+pub(crate) fn test_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("world_image_file_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Asserts that `a` is within `tolerance` of `b`, relative to `b` when that's more than 1
+pub(crate) fn assert_close(a: f64, b: f64, tolerance: f64) {
+    assert!(
+        (a - b).abs() <= tolerance * b.abs().max(1.),
+        "{} != {}",
+        a,
+        b
+    );
+}
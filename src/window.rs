@@ -0,0 +1,97 @@
+use crate::WorldFile;
+
+/// A rectangle of pixels in an image, e.g. to crop it to
+///
+/// The offsets are of the top left pixel of the window, in the image. They can be negative, or
+/// past the edge of the image, e.g. to pad it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Window {
+    pub col_off: i64,
+    pub row_off: i64,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Window {
+    pub fn new(col_off: i64, row_off: i64, width: u32, height: u32) -> Self {
+        Window {
+            col_off,
+            row_off,
+            width,
+            height,
+        }
+    }
+}
+
+impl WorldFile {
+    /// The world file for this image, cropped so that pixel (`x_off`, `y_off`) is the new top
+    /// left pixel
+    ///
+    /// The pixel size, rotation, and skew are unchanged, so this is correct for rotated &
+    /// skewed images too. Negative offsets add pixels above & left of the image.
+    /// ```
+    /// # use world_image_file::WorldFile;
+    /// let w = WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n").unwrap();
+    /// let cropped = w.cropped(100, 50);
+    /// assert_eq!(cropped.to_string(), "32\n0\n0\n-32\n694400\n4574400\n");
+    /// ```
+    pub fn cropped(&self, x_off: i64, y_off: i64) -> WorldFile {
+        *self * WorldFile::from_translation(x_off as f64, y_off as f64)
+    }
+
+    /// The world file for this window of the image
+    ///
+    /// The same as [`cropped`](#method.cropped), with the window's offsets. The window's size
+    /// is then the size of the new image, e.g. for [`corners`](#method.corners).
+    /// ```
+    /// # use world_image_file::{WorldFile, Window};
+    /// # let w = WorldFile::from_string("32.0\n0.0\n0.0\n-32.0\n691200.0\n4576000.0\n").unwrap();
+    /// let window = Window::new(100, 50, 10, 20);
+    /// let bounds = w.windowed(&window).bounds(window.width, window.height);
+    /// assert_eq!((bounds.min_x, bounds.max_y), (694384., 4574416.));
+    /// ```
+    pub fn windowed(&self, window: &Window) -> WorldFile {
+        self.cropped(window.col_off, window.row_off)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cropped() {
+        let w = WorldFile::new(0.5, 0., 0., -0.5, -179.75, 89.75).unwrap();
+        assert_eq!(w.cropped(0, 0), w);
+        assert_eq!(
+            w.cropped(360, 180),
+            WorldFile::new(0.5, 0., 0., -0.5, 0.25, -0.25).unwrap()
+        );
+        assert_eq!(
+            w.cropped(-2, -4),
+            WorldFile::new(0.5, 0., 0., -0.5, -180.75, 91.75).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_skewed() {
+        // Each pixel of the window is at the same world coordinates as in the image
+        let w =
+            WorldFile::from_gdal_geotransform([691184., 30., -2.5, 4576016., 1.25, -28.]).unwrap();
+        let window = Window::new(37, 12, 100, 50);
+        let windowed = w.windowed(&window);
+        for &(x, y) in &[(0., 0.), (99., 0.), (0., 49.), (12.5, 7.25)] {
+            let image = (x + window.col_off as f64, y + window.row_off as f64);
+            assert_eq!(windowed.image_to_world((x, y)), w.image_to_world(image));
+        }
+        assert_eq!(
+            windowed.world_to_image(w.image_to_world((37., 12.))),
+            (0., 0.)
+        );
+        assert_eq!(windowed.pixel_size(), w.pixel_size());
+
+        // A window covering the whole image is the whole image
+        let all = w.windowed(&Window::new(0, 0, 640, 480));
+        assert_eq!(all.corners(640, 480), w.corners(640, 480));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::WorldFile;
+use std::fmt;
+
+/// How to format the numbers when writing a world file
+///
+/// Used with [`WorldFile::to_string_with`](crate::WorldFile::to_string_with) and friends. The
+/// default is [`shortest`](#method.shortest), which is what `to_string()` uses.
+/// ```
+/// use world_image_file::{WorldFile, WriteOptions};
+/// let w = WorldFile::from_string("32\n0\n0\n-32\n691200\n4576000\n").unwrap();
+/// assert_eq!(
+///     w.to_string_with(&WriteOptions::fixed(10)),
+///     "32.0000000000\n0.0000000000\n0.0000000000\n-32.0000000000\n691200.0000000000\n4576000.0000000000\n"
+/// );
+/// assert_eq!(
+///     w.to_string_with(&WriteOptions::scientific()),
+///     "3.2e1\n0e0\n0e0\n-3.2e1\n6.912e5\n4.576e6\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteOptions {
+    format: NumberFormat,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NumberFormat {
+    Shortest,
+    Fixed(usize),
+    Scientific,
+}
+
+impl WriteOptions {
+    /// The shortest decimal number which reads back as exactly the same value
+    ///
+    /// Writing and then reading a world file with this always gives bit-identical values.
+    pub fn shortest() -> Self {
+        WriteOptions {
+            format: NumberFormat::Shortest,
+        }
+    }
+
+    /// Always this many decimal places. ESRI software and GDAL write 10 decimal places.
+    ///
+    /// This can lose precision.
+    pub fn fixed(decimals: usize) -> Self {
+        WriteOptions {
+            format: NumberFormat::Fixed(decimals),
+        }
+    }
+
+    /// Scientific notation (e.g. `6.912e5`), with the shortest mantissa which reads back as
+    /// exactly the same value
+    pub fn scientific() -> Self {
+        WriteOptions {
+            format: NumberFormat::Scientific,
+        }
+    }
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions::shortest()
+    }
+}
+
+/// Write the file contents of `w` to `f`
+pub(crate) fn format(
+    w: &WorldFile,
+    options: &WriteOptions,
+    f: &mut impl fmt::Write,
+) -> fmt::Result {
+    let values = [
+        w.x_scale, w.y_skew, w.x_skew, w.y_scale, w.x_coord, w.y_coord,
+    ];
+    for value in values.iter() {
+        match options.format {
+            NumberFormat::Shortest => writeln!(f, "{}", value)?,
+            NumberFormat::Fixed(decimals) => writeln!(f, "{:.*}", decimals, value)?,
+            NumberFormat::Scientific => writeln!(f, "{:e}", value)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn any_value() -> impl Strategy<Value = f64> {
+        use proptest::num::f64::{NEGATIVE, NORMAL, POSITIVE, SUBNORMAL, ZERO};
+        POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO
+    }
+
+    fn assert_bit_identical(a: &WorldFile, b: &WorldFile) {
+        let bits = |w: &WorldFile| {
+            [
+                w.x_scale, w.y_skew, w.x_skew, w.y_scale, w.x_coord, w.y_coord,
+            ]
+            .iter()
+            .map(|v| v.to_bits())
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(bits(a), bits(b), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_fixed() {
+        let w = WorldFile::new(0.1, 0., -0., -0.1, 1.23456789012345, -5.).unwrap();
+        assert_eq!(
+            w.to_string_with(&WriteOptions::fixed(3)),
+            "0.100\n0.000\n-0.000\n-0.100\n1.235\n-5.000\n"
+        );
+    }
+
+    #[test]
+    fn test_negative_zero() {
+        let w = WorldFile::new(1., -0., 0., -1., -0., 0.).unwrap();
+        assert_bit_identical(&WorldFile::from_string(w.to_string()).unwrap(), &w);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_shortest_round_trip(
+            x_scale in any_value(),
+            y_skew in any_value(),
+            x_skew in any_value(),
+            y_scale in any_value(),
+            x_coord in any_value(),
+            y_coord in any_value(),
+        ) {
+            let w = WorldFile { x_scale, y_skew, x_skew, y_scale, x_coord, y_coord };
+            prop_assume!(w.determinant() != 0.);
+
+            assert_bit_identical(&WorldFile::from_string(w.to_string()).unwrap(), &w);
+            let scientific = w.to_string_with(&WriteOptions::scientific());
+            assert_bit_identical(&WorldFile::from_string(scientific).unwrap(), &w);
+        }
+    }
+}